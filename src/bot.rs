@@ -21,23 +21,7 @@ impl SandwichBot {
         let config_arc = Arc::new(config);
         
         // Initialiser le gestionnaire DEX
-        let config_clone = BotConfig {
-            rpc_url: config_arc.rpc_url.clone(),
-            ws_url: config_arc.ws_url.clone(),
-            jito_urls: config_arc.jito_urls.clone(),
-            keypair: config_arc.keypair.insecure_clone(),
-            position_size_lamports: config_arc.position_size_lamports,
-            min_profit_percent: config_arc.min_profit_percent,
-            max_slippage_bps: config_arc.max_slippage_bps,
-            priority_fee_lamports: config_arc.priority_fee_lamports,
-            jito_tip_lamports: config_arc.jito_tip_lamports,
-            max_position_size_pct: config_arc.max_position_size_pct,
-            min_liquidity_usd: config_arc.min_liquidity_usd,
-            test_mode: config_arc.test_mode,
-            min_mcap_usd: config_arc.min_mcap_usd,
-            max_mcap_usd: config_arc.max_mcap_usd,
-        };
-        let dex_manager = DexManager::new(config_clone).await?;
+        let dex_manager = DexManager::new(Arc::clone(&config_arc)).await?;
         
         // Créer les engines
         let user_token_accounts = dex_manager.user_token_accounts.clone();
@@ -94,7 +78,7 @@ impl SandwichBot {
             }
             Err(e) => {
                 log::error!("❌ Erreur WebSocket: {}", e);
-                return Err(e);
+                Err(e)
             }
         }
     }
@@ -1,8 +1,36 @@
-use solana_sdk::signature::Keypair;
+use anyhow::{anyhow, Result};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::{read_keypair_file, Keypair}};
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::{HashMap, HashSet};
+use crate::types::{DexType, SolPriceSource};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 // ============================================================================
 // CONFIGURATION
 // ============================================================================
+
+/// Bande d'impact et montant minimum pour qu'une analyse soit signalée comme opportunité
+#[derive(Debug, Clone)]
+pub struct OpportunityFilter {
+    pub min_mcap_impact_pct: f64,
+    // Au-delà : erreur de parsing, pool ultra fine ou token invendable, presque jamais réel
+    pub max_mcap_impact_pct: f64,
+    pub min_invested_usd: f64,
+}
+
+impl Default for OpportunityFilter {
+    fn default() -> Self {
+        Self {
+            min_mcap_impact_pct: 2.0,
+            max_mcap_impact_pct: 200.0,
+            min_invested_usd: 100.0,
+        }
+    }
+}
+
 pub struct BotConfig {
     pub rpc_url: String,
     pub ws_url: String,
@@ -15,51 +43,775 @@ pub struct BotConfig {
     pub jito_tip_lamports: u64,
     pub max_position_size_pct: f64,
     pub min_liquidity_usd: f64,
+    // Seuils de liquidité par DEX (remplacent min_liquidity_usd pour les DEX présents)
+    pub min_liquidity_per_dex: HashMap<DexType, f64>,
     // Mode test - désactive l'envoi de transactions
     pub test_mode: bool,
     pub min_mcap_usd: f64,
     pub max_mcap_usd: f64,
+    // Budget temps total (tous appels RPC et retries confondus) pour analyser une signature
+    pub analysis_budget_ms: u64,
+    pub rpc_max_retries: u32,
+    // Nombre maximum d'analyses de transactions en parallèle
+    pub max_concurrent_analyses: usize,
+    // Ratio max entre la valeur des deux côtés d'un pool (au-delà : pool vidé ou fraîchement seedé)
+    pub max_reserve_ratio: f64,
+    // Debug : dossier où écrire le JSON des transactions dont l'analyse échoue (désactivé par défaut)
+    pub dump_failed_tx_to: Option<PathBuf>,
+    // Export CSV des résultats d'analyse (désactivé par défaut), écrit par lots de analysis_csv_flush_every
+    pub analysis_csv_path: Option<PathBuf>,
+    pub analysis_csv_flush_every: usize,
+    // Ignorer avant analyse les transactions sans aucun mouvement de token
+    pub skip_non_swap_transactions: bool,
+    // Analyser les swaps Pump.fun (bonding curve) au lieu de les rejeter comme non supportés
+    pub analyze_pumpfun: bool,
+    // Ignorer les transactions signées par notre wallet (autre instance, trading manuel...)
+    pub skip_own_transactions: bool,
+    // Appliquer les frais du pool (fee_bps) dans les calculs d'impact et de sortie AMM
+    pub fee_adjusted_impact: bool,
+    // Dernier prix SOL persisté entre deux lancements (None = désactivé)
+    pub sol_price_cache_path: Option<PathBuf>,
+    pub sol_price_cache_max_age_secs: u64,
+    // Sources du prix SOL, essayées dans l'ordre jusqu'à obtenir un prix plausible
+    pub sol_price_sources: Vec<SolPriceSource>,
+    pub pyth_sol_usd_account: Pubkey,
+    pub pyth_max_staleness_secs: u64,
+    pub sol_usdc_pool: Pubkey, // Pool Raydium V4 WSOL/USDC de la source OnChainPool
+    // Bornes de plausibilité du prix SOL et prix utilisé si aucune source ne répond au démarrage
+    pub sol_price_min: f64,
+    pub sol_price_max: f64,
+    pub sol_price_fallback: f64,
+    // Détection des allers-retours (achat + vente du même token dans une tx)
+    pub detect_round_trips: bool,
+    pub round_trip_max_net_ratio: f64, // |variation nette| / flux brut en dessous duquel c'est un aller-retour
+    // Encodage des getTransaction (pré-filtre et analyse) : Json (message brut) ou JsonParsed.
+    // L'analyse n'utilise que les balances et les clés de comptes, disponibles dans les deux.
+    pub transaction_encoding: UiTransactionEncoding,
+    // Bornes (unités UI) d'un changement de balance retenu comme tokens reçus
+    pub min_tokens_received: f64,
+    pub max_tokens_received: f64,
+    // Fallback sur les ATAs du signataire quand aucune balance ne lui est attribuée
+    pub tokens_received_fallback: bool,
+    // N'attribuer l'investissement qu'au mint de quote du pool swappé
+    pub attribute_investment_to_quote_mint: bool,
+    // Coupe-circuit des parsers : désactive un DEX après N échecs consécutifs, pendant le cooldown
+    pub parser_failure_threshold: u32,
+    pub parser_cooldown_secs: u64,
+    // Âge max d'une transaction en file avant analyse ; 0 = pas de limite
+    pub max_queue_age_ms: u64,
+    // Log détaillé des pools candidates et du choix de la pool dominante
+    pub log_pool_selection: bool,
+    // Owners à ne jamais considérer comme des pools (faux positifs de l'heuristique)
+    pub pool_owner_denylist: HashSet<Pubkey>,
+    // Collecteur OTLP des traces d'analyse (nécessite la feature `otel`)
+    pub otlp_endpoint: Option<String>,
+    // Port du endpoint Prometheus `/metrics` (None = désactivé)
+    pub metrics_port: Option<u16>,
+    // Dédoublonnage des pools extraites d'une transaction avant le choix de la pool dominante
+    pub dedup_extracted_pools: bool,
+    // Part max de la réserve token du pool que la cible peut recevoir avant que la détection soit jugée suspecte
+    pub max_received_reserve_share: f64,
+    // Ignorer les pools aux réserves nulles et se rabattre sur la suivante plus dominante
+    pub skip_zero_reserve_pools: bool,
+    pub opportunity_filter: OpportunityFilter,
+    // Meteora DLMM : récupérer les bin arrays (actif ± rayon) pour un impact calculé bin par bin
+    pub fetch_dlmm_bin_arrays: bool,
+    pub dlmm_bin_array_radius: i64,
+    // Orca : vérifier que les vaults décodés de la whirlpool détiennent bien ses mints (détection de dérive de layout)
+    pub verify_orca_vaults: bool,
+    // Pools à frais dynamiques (Meteora DLMM) : appliquer le frais effectif à la taille du swap plutôt que le frais de base
+    pub dynamic_fee_impact: bool,
+    // Fetch des décimales d'un mint en échec : supposer default_decimals (pool marquée decimals_assumed)
+    // plutôt que faire échouer le parsing ; le fallback est gardé en cache le temps du TTL
+    pub assume_default_decimals: bool,
+    pub default_decimals: u8,
+    pub decimals_fallback_ttl_secs: u64,
+    // Durée de validité d'une pool en cache (réserves) avant re-fetch
+    pub pool_cache_ttl_secs: u64,
+    // Durée de cache des pools découvertes par mint (getProgramAccounts, coûteux)
+    pub mint_pools_cache_ttl_secs: u64,
+    // Journal des TransactionLog : JSON Lines par défaut, format lisible `a | b | ...` si pretty
+    pub transaction_log_path: PathBuf,
+    pub transaction_log_pretty: bool,
+    // Analyse hors-ligne d'un JSON de transaction (--analyze-file) : prix SOL fixe et supplies connues (unités UI)
+    pub offline_sol_price: f64,
+    pub offline_supplies: HashMap<Pubkey, f64>,
+    // Analyser les markets OpenBook v2 (carnet d'ordres) au lieu de les rejeter comme non supportés
+    pub analyze_openbook_v2: bool,
+    // Dédoublonnage des signatures WebSocket : taille max (0 = désactivé), fenêtre, période du log de métriques (0 = jamais)
+    pub seen_signatures_capacity: usize,
+    pub seen_signatures_ttl_secs: u64,
+    pub seen_signatures_report_secs: u64,
+    // Parsing de pool : vaults et mints en un seul getMultipleAccounts au lieu d'un appel par compte
+    pub batch_account_fetches: bool,
+    // Transactions relayées : swapper = signataire dont les tokens bougent le plus (sinon le fee payer)
+    pub resolve_swapper_from_balances: bool,
+    // Impact : partir de la quote réellement entrée dans le pool (si connue) plutôt que des tokens reçus
+    pub impact_from_observed_quote_in: bool,
+    // Joindre au résultat les réserves, prix et deltas ayant produit l'impact (SandwichAnalysisResult::debug_detail)
+    pub verbose_impact_detail: bool,
+    // Pool retenue : écarter celles dont la quote n'est pas le mint réellement payé par la cible (WSOL/SOL ou USDC)
+    pub reject_off_route_quote_pools: bool,
+    // WebSocket : délai max entre deux tentatives de reconnexion (backoff exponentiel depuis 1s)
+    pub websocket_reconnect_max_backoff_secs: u64,
+}
+
+/// Fichier de configuration TOML (`BotConfig::from_file`) : champs absents = valeurs par défaut.
+/// Couvre tous les champs de `BotConfig` ; chacun peut être remplacé par la variable d'environnement
+/// de même nom en majuscules (listes séparées par des virgules, tables au format `clé=valeur,...`).
+/// Le keypair est référencé par chemin (format JSON de `solana-keygen`), jamais en base58 inline :
+/// la clé privée base58 (`PRIVATE_KEY`) n'est lue que dans l'environnement.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub rpc_url: Option<String>,
+    pub ws_url: Option<String>,
+    pub jito_urls: Option<Vec<String>>,
+    pub keypair_path: Option<PathBuf>,
+    pub position_size_lamports: Option<u64>,
+    pub min_profit_percent: Option<f64>,
+    pub max_slippage_bps: Option<u64>,
+    pub priority_fee_lamports: Option<u64>,
+    pub jito_tip_lamports: Option<u64>,
+    pub max_position_size_pct: Option<f64>,
+    pub min_liquidity_usd: Option<f64>,
+    pub min_liquidity_per_dex: Option<HashMap<String, f64>>, // Nom de variante DexType -> seuil USD
+    pub test_mode: Option<bool>,
+    pub min_mcap_usd: Option<f64>,
+    pub max_mcap_usd: Option<f64>,
+    pub analysis_budget_ms: Option<u64>,
+    pub rpc_max_retries: Option<u32>,
+    pub max_concurrent_analyses: Option<usize>,
+    pub max_reserve_ratio: Option<f64>,
+    pub transaction_encoding: Option<String>, // "json" ou "jsonParsed"
+    pub max_queue_age_ms: Option<u64>,
+    pub pool_owner_denylist: Option<Vec<String>>,
+    pub sol_price_cache_path: Option<PathBuf>, // "" = persistance désactivée
+    pub sol_price_cache_max_age_secs: Option<u64>,
+    pub sol_price_min: Option<f64>,
+    pub sol_price_max: Option<f64>,
+    pub sol_price_fallback: Option<f64>,
+    pub pool_cache_ttl_secs: Option<u64>,
+    pub mint_pools_cache_ttl_secs: Option<u64>,
+    pub min_tokens_received: Option<f64>,
+    pub max_tokens_received: Option<f64>,
+    pub parser_failure_threshold: Option<u32>, // 0 = coupe-circuit désactivé
+    pub parser_cooldown_secs: Option<u64>,
+    pub fetch_dlmm_bin_arrays: Option<bool>,
+    pub dlmm_bin_array_radius: Option<i64>, // Bin arrays lus de part et d'autre du bin actif
+    pub verify_orca_vaults: Option<bool>,
+    pub analysis_csv_flush_every: Option<usize>,
+    pub min_mcap_impact_pct: Option<f64>, // OpportunityFilter
+    pub max_mcap_impact_pct: Option<f64>,
+    pub min_invested_usd: Option<f64>,
+    pub seen_signatures_capacity: Option<usize>, // 0 = déduplication désactivée
+    pub seen_signatures_ttl_secs: Option<u64>,
+    pub seen_signatures_report_secs: Option<u64>,
+    pub skip_non_swap_transactions: Option<bool>,
+    pub analyze_pumpfun: Option<bool>,
+    pub skip_own_transactions: Option<bool>,
+    pub fee_adjusted_impact: Option<bool>,
+    pub pyth_max_staleness_secs: Option<u64>,
+    pub detect_round_trips: Option<bool>,
+    pub round_trip_max_net_ratio: Option<f64>,
+    pub tokens_received_fallback: Option<bool>,
+    pub attribute_investment_to_quote_mint: Option<bool>,
+    pub log_pool_selection: Option<bool>,
+    pub dedup_extracted_pools: Option<bool>,
+    pub max_received_reserve_share: Option<f64>,
+    pub skip_zero_reserve_pools: Option<bool>,
+    pub dynamic_fee_impact: Option<bool>,
+    pub assume_default_decimals: Option<bool>,
+    pub default_decimals: Option<u8>,
+    pub decimals_fallback_ttl_secs: Option<u64>,
+    pub transaction_log_pretty: Option<bool>,
+    pub analyze_openbook_v2: Option<bool>,
+    pub batch_account_fetches: Option<bool>,
+    pub resolve_swapper_from_balances: Option<bool>,
+    pub impact_from_observed_quote_in: Option<bool>,
+    pub verbose_impact_detail: Option<bool>,
+    pub reject_off_route_quote_pools: Option<bool>,
+    pub websocket_reconnect_max_backoff_secs: Option<u64>,
+    pub sol_price_sources: Option<Vec<String>>, // Noms de SolPriceSource, dans l'ordre d'essai
+    pub pyth_sol_usd_account: Option<String>,
+    pub sol_usdc_pool: Option<String>,
+    pub transaction_log_path: Option<PathBuf>,
+    pub dump_failed_tx_to: Option<PathBuf>,
+    pub analysis_csv_path: Option<PathBuf>, // Variable ANALYSIS_CSV_PATH (ou ANALYSIS_CSV)
+    pub otlp_endpoint: Option<String>,
+    pub metrics_port: Option<u16>,
+    pub offline_sol_price: Option<f64>,
+    pub offline_supplies: Option<HashMap<String, f64>>, // Mint base58 -> supply (unités UI)
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Lecture de {} impossible: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| anyhow!("Fichier de config {} invalide: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        // Passer par toml::Value : les tables (min_liquidity_per_dex...) sont écrites après les valeurs simples
+        let text = toml::Value::try_from(self)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .map_err(|e| anyhow!("Sérialisation de la config impossible: {}", e))?;
+        std::fs::write(path, text).map_err(|e| anyhow!("Écriture de {} impossible: {}", path.display(), e))
+    }
+}
+
+/// Source des variables d'environnement : le vrai environnement du process, ou une table en test
+/// (`setenv` concurrent à `getenv` sur d'autres threads n'est pas sûr)
+struct Env<F: Fn(&str) -> Option<String>>(F);
+
+impl<F: Fn(&str) -> Option<String>> Env<F> {
+    /// Variable non vide
+    fn var(&self, var: &str) -> Option<String> {
+        (self.0)(var).filter(|v| !v.is_empty())
+    }
+
+    /// Variable non vide parsée vers le type du champ (erreur explicite si invalide)
+    fn parse<T: FromStr>(&self, var: &str) -> Result<Option<T>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.var(var)
+            .map(|value| value.parse().map_err(|e| anyhow!("{} invalide ({}): {}", var, value, e)))
+            .transpose()
+    }
+
+    /// Chemin optionnel : définie mais vide = désactivé (`Some(None)`), absente = `None`
+    fn optional_path(&self, var: &str) -> Option<Option<PathBuf>> {
+        (self.0)(var).map(|value| Some(PathBuf::from(value.trim())).filter(|path| !path.as_os_str().is_empty()))
+    }
+
+    /// Liste séparée par des virgules (`a,b,c`)
+    fn list(&self, var: &str) -> Option<Vec<String>> {
+        self.var(var).map(|value| value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect())
+    }
+
+    /// Table `clé=valeur` séparée par des virgules (`RaydiumV4=50000,MeteoraDLMM=5000`)
+    fn table(&self, var: &str) -> Result<Option<HashMap<String, f64>>> {
+        let Some(items) = self.list(var) else { return Ok(None) };
+        items.iter()
+            .map(|item| {
+                let (key, value) = item.split_once('=')
+                    .ok_or_else(|| anyhow!("{} : entrée {} sans '=' (format clé=valeur,...)", var, item))?;
+                let value = value.trim().parse()
+                    .map_err(|e| anyhow!("{} : valeur invalide pour {} ({})", var, key.trim(), e))?;
+                Ok((key.trim().to_string(), value))
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+}
+
+/// DexType à partir de son nom de variante (clé de `min_liquidity_per_dex` dans le fichier)
+fn dex_type_from_name(name: &str) -> Result<DexType> {
+    DexType::deserialize(name.into_deserializer())
+        .map_err(|e: serde::de::value::Error| anyhow!("DEX inconnu dans min_liquidity_per_dex: {} ({})", name, e))
+}
+
+/// Encodage getTransaction utilisable par l'analyse (`json` ou `jsonParsed`)
+fn transaction_encoding_from_name(name: &str) -> Result<UiTransactionEncoding> {
+    match name {
+        "json" => Ok(UiTransactionEncoding::Json),
+        "jsonParsed" => Ok(UiTransactionEncoding::JsonParsed),
+        other => Err(anyhow!("transaction_encoding {} non supporté (json ou jsonParsed)", other)),
+    }
+}
+
+/// Source de prix SOL par nom de variante (`CoinGecko`, `Pyth`, `OnChainPool`)
+fn sol_price_source_from_name(name: &str) -> Result<SolPriceSource> {
+    match name {
+        "CoinGecko" => Ok(SolPriceSource::CoinGecko),
+        "Pyth" => Ok(SolPriceSource::Pyth),
+        "OnChainPool" => Ok(SolPriceSource::OnChainPool),
+        other => Err(anyhow!("sol_price_sources : source {} inconnue (CoinGecko, Pyth ou OnChainPool)", other)),
+    }
+}
+
+/// Pubkey base58 lue dans le champ `field` du fichier
+fn pubkey_from_config(field: &str, value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| anyhow!("{} : adresse invalide {} ({})", field, value, e))
+}
+
+/// Décode le keypair de `var` (base58 de 64 octets), avec une erreur explicite au lieu d'un panic
+fn keypair_from_env(var: &str, encoded: Option<String>) -> Result<Keypair> {
+    let encoded = encoded
+        .ok_or_else(|| anyhow!("Variable d'environnement {} manquante (clé privée base58 requise)", var))?;
+    let encoded = encoded.trim();
+    if encoded.is_empty() {
+        return Err(anyhow!("Variable d'environnement {} vide (clé privée base58 requise)", var));
+    }
+    let bytes = bs58::decode(encoded).into_vec()
+        .map_err(|e| anyhow!("{} : décodage base58 impossible ({})", var, e))?;
+    if bytes.len() != 64 {
+        return Err(anyhow!(
+            "{} : clé de {} octets, 64 attendus (clé tronquée ou clé publique collée à la place de la clé privée ?)",
+            var, bytes.len()
+        ));
+    }
+    Keypair::from_bytes(&bytes).map_err(|e| anyhow!("{} : keypair invalide ({})", var, e))
 }
 
 impl BotConfig {
+    /// Équivalent de `try_new` qui panique sur une configuration invalide
     pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| panic!("{:#}", e))
+    }
+
+    /// Charge la configuration depuis l'environnement ; erreur descriptive si PRIVATE_KEY est absente ou invalide
+    pub fn try_new() -> Result<Self> {
+        Self::from_sources(ConfigFile::default())
+    }
+
+    /// Charge la configuration depuis un fichier TOML. Priorité : variables d'environnement > fichier > défaut
+    /// (PRIVATE_KEY, si définie, remplace `keypair_path`).
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = ConfigFile::load(path)?;
+        log::info!("📄 Fichier de config: {}", path.display());
+        Self::from_sources(file)
+    }
+
+    fn from_sources(file: ConfigFile) -> Result<Self> {
+        Self::from_sources_with(file, |var| std::env::var(var).ok())
+    }
+
+    /// `from_sources` avec une source de variables d'environnement injectée (tests)
+    pub(crate) fn from_sources_with(file: ConfigFile, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let env = Env(env);
         // Charger les variables d'environnement - Utiliser Helius pour de meilleures performances
-        let rpc_url = std::env::var("RPC_URL")
-            .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
-        let ws_url = std::env::var("WS_URL")
-            .unwrap_or_else(|_| "wss://api.mainnet-beta.solana.com".to_string());
+        let rpc_url = env.var("RPC_URL")
+            .or(file.rpc_url)
+            .unwrap_or_else(|| "https://api.mainnet-beta.solana.com".to_string());
+        let ws_url = env.var("WS_URL")
+            .or(file.ws_url)
+            .unwrap_or_else(|| "wss://api.mainnet-beta.solana.com".to_string());
         
+        let keypair_path = env.var("KEYPAIR_PATH").map(PathBuf::from).or(file.keypair_path);
+        let keypair = match (&keypair_path, env.var("PRIVATE_KEY")) {
+            (Some(path), None) => read_keypair_file(path)
+                .map_err(|e| anyhow!("keypair_path {} illisible: {}", path.display(), e))?,
+            _ => keypair_from_env("PRIVATE_KEY", env.var("PRIVATE_KEY"))?,
+        };
+
+        let filter_defaults = OpportunityFilter::default();
+        let opportunity_filter = OpportunityFilter {
+            min_mcap_impact_pct: env.parse("MIN_MCAP_IMPACT_PCT")?.or(file.min_mcap_impact_pct).unwrap_or(filter_defaults.min_mcap_impact_pct),
+            max_mcap_impact_pct: env.parse("MAX_MCAP_IMPACT_PCT")?.or(file.max_mcap_impact_pct).unwrap_or(filter_defaults.max_mcap_impact_pct),
+            min_invested_usd: env.parse("MIN_INVESTED_USD")?.or(file.min_invested_usd).unwrap_or(filter_defaults.min_invested_usd),
+        };
+
+        let min_liquidity_per_dex = env.table("MIN_LIQUIDITY_PER_DEX")?.or(file.min_liquidity_per_dex).unwrap_or_default().into_iter()
+            .map(|(dex, min)| Ok((dex_type_from_name(&dex)?, min)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let pool_owner_denylist = env.list("POOL_OWNER_DENYLIST").or(file.pool_owner_denylist).unwrap_or_default().iter()
+            .map(|owner| pubkey_from_config("pool_owner_denylist", owner))
+            .collect::<Result<HashSet<_>>>()?;
+        let transaction_encoding = env.var("TRANSACTION_ENCODING").or(file.transaction_encoding)
+            .map(|name| transaction_encoding_from_name(&name))
+            .transpose()?
+            .unwrap_or(UiTransactionEncoding::JsonParsed);
+        let sol_price_sources = env.list("SOL_PRICE_SOURCES").or(file.sol_price_sources)
+            .map(|names| names.iter().map(|name| sol_price_source_from_name(name)).collect::<Result<Vec<_>>>())
+            .transpose()?
+            .unwrap_or_else(|| vec![SolPriceSource::CoinGecko, SolPriceSource::Pyth, SolPriceSource::OnChainPool]);
+        let pyth_sol_usd_account = env.var("PYTH_SOL_USD_ACCOUNT").or(file.pyth_sol_usd_account)
+            .map(|account| pubkey_from_config("pyth_sol_usd_account", &account))
+            .transpose()?
+            .unwrap_or(solana_sdk::pubkey!("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE"));
+        let sol_usdc_pool = env.var("SOL_USDC_POOL").or(file.sol_usdc_pool)
+            .map(|pool| pubkey_from_config("sol_usdc_pool", &pool))
+            .transpose()?
+            .unwrap_or(solana_sdk::pubkey!("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"));
+        let offline_supplies = env.table("OFFLINE_SUPPLIES")?.or(file.offline_supplies).unwrap_or_default().into_iter()
+            .map(|(mint, supply)| Ok((pubkey_from_config("offline_supplies", &mint)?, supply)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
         log::info!("🔧 Configuration chargée:");
         log::info!(" 📡 RPC URL: {}", rpc_url);
         log::info!(" 🌐 WS URL: {}", ws_url);
         
-        Self {
+        let config = Self {
             rpc_url,
             ws_url,
             
-            jito_urls: vec![
+            jito_urls: env.list("JITO_URLS").or(file.jito_urls).unwrap_or_else(|| vec![
                 "https://mainnet.block-engine.jito.wtf/api/v1/bundles".to_string(),
                 "https://amsterdam.mainnet.block-engine.jito.wtf/api/v1/bundles".to_string(),
                 "https://frankfurt.mainnet.block-engine.jito.wtf/api/v1/bundles".to_string(),
                 "https://ny.mainnet.block-engine.jito.wtf/api/v1/bundles".to_string(),
                 "https://tokyo.mainnet.block-engine.jito.wtf/api/v1/bundles".to_string(),
-            ],
+            ]),
     
-            keypair: Keypair::from_base58_string(
-                &std::env::var("PRIVATE_KEY").expect("PRIVATE_KEY env var requis")
-            ),
+            keypair,
     
-            position_size_lamports: 670_000_000, // ~100$ @ 150$ SOL
-            min_profit_percent: 10.0,
-            max_slippage_bps: 200,
-            priority_fee_lamports: 500_000,
-            jito_tip_lamports: 50_000,
-            max_position_size_pct: 5.0,
-            min_liquidity_usd: 1_000.0, // Plus bas pour les petits tokens
+            position_size_lamports: env.parse("POSITION_SIZE_LAMPORTS")?.or(file.position_size_lamports).unwrap_or(670_000_000), // ~100$ @ 150$ SOL
+            min_profit_percent: env.parse("MIN_PROFIT_PERCENT")?.or(file.min_profit_percent).unwrap_or(10.0),
+            max_slippage_bps: env.parse("MAX_SLIPPAGE_BPS")?.or(file.max_slippage_bps).unwrap_or(200),
+            priority_fee_lamports: env.parse("PRIORITY_FEE_LAMPORTS")?.or(file.priority_fee_lamports).unwrap_or(500_000),
+            jito_tip_lamports: env.parse("JITO_TIP_LAMPORTS")?.or(file.jito_tip_lamports).unwrap_or(50_000),
+            max_position_size_pct: env.parse("MAX_POSITION_SIZE_PCT")?.or(file.max_position_size_pct).unwrap_or(5.0),
+            min_liquidity_usd: env.parse("MIN_LIQUIDITY_USD")?.or(file.min_liquidity_usd).unwrap_or(1_000.0), // Plus bas pour les petits tokens
+            min_liquidity_per_dex,
             // Mode test activé par défaut
-            test_mode: true,
-        min_mcap_usd: 500_000.0,  // Min 500k mcap
-        max_mcap_usd: 10_000_000.0, // Max 10M mcap
+            test_mode: env.parse("TEST_MODE")?.or(file.test_mode).unwrap_or(true),
+            min_mcap_usd: env.parse("MIN_MCAP_USD")?.or(file.min_mcap_usd).unwrap_or(500_000.0),  // Min 500k mcap
+            max_mcap_usd: env.parse("MAX_MCAP_USD")?.or(file.max_mcap_usd).unwrap_or(10_000_000.0), // Max 10M mcap
+            analysis_budget_ms: env.parse("ANALYSIS_BUDGET_MS")?.or(file.analysis_budget_ms).unwrap_or(5_000),
+            rpc_max_retries: env.parse("RPC_MAX_RETRIES")?.or(file.rpc_max_retries).unwrap_or(3),
+            max_concurrent_analyses: env.parse("MAX_CONCURRENT_ANALYSES")?.or(file.max_concurrent_analyses).unwrap_or(64),
+            max_reserve_ratio: env.parse("MAX_RESERVE_RATIO")?.or(file.max_reserve_ratio).unwrap_or(1_000_000.0),
+            dump_failed_tx_to: env.var("DUMP_FAILED_TX_TO").map(PathBuf::from).or(file.dump_failed_tx_to),
+            analysis_csv_path: env.var("ANALYSIS_CSV_PATH").or_else(|| env.var("ANALYSIS_CSV")).map(PathBuf::from)
+                .or(file.analysis_csv_path),
+            analysis_csv_flush_every: env.parse("ANALYSIS_CSV_FLUSH_EVERY")?.or(file.analysis_csv_flush_every).unwrap_or(50),
+            skip_non_swap_transactions: env.parse("SKIP_NON_SWAP_TRANSACTIONS")?.or(file.skip_non_swap_transactions).unwrap_or(true),
+            analyze_pumpfun: env.parse("ANALYZE_PUMPFUN")?.or(file.analyze_pumpfun).unwrap_or(true),
+            skip_own_transactions: env.parse("SKIP_OWN_TRANSACTIONS")?.or(file.skip_own_transactions).unwrap_or(true),
+            fee_adjusted_impact: env.parse("FEE_ADJUSTED_IMPACT")?.or(file.fee_adjusted_impact).unwrap_or(true),
+            sol_price_cache_path: env.optional_path("SOL_PRICE_CACHE_PATH")
+                .or(file.sol_price_cache_path.map(|path| Some(path).filter(|path| !path.as_os_str().is_empty())))
+                .unwrap_or_else(|| Some(PathBuf::from("sol_price_cache.json"))),
+            sol_price_cache_max_age_secs: env.parse("SOL_PRICE_CACHE_MAX_AGE_SECS")?.or(file.sol_price_cache_max_age_secs).unwrap_or(3_600),
+            sol_price_sources,
+            pyth_sol_usd_account,
+            pyth_max_staleness_secs: env.parse("PYTH_MAX_STALENESS_SECS")?.or(file.pyth_max_staleness_secs).unwrap_or(120),
+            sol_usdc_pool,
+            sol_price_min: env.parse("SOL_PRICE_MIN")?.or(file.sol_price_min).unwrap_or(50.0),
+            sol_price_max: env.parse("SOL_PRICE_MAX")?.or(file.sol_price_max).unwrap_or(500.0),
+            sol_price_fallback: env.parse("SOL_PRICE_FALLBACK")?.or(file.sol_price_fallback).unwrap_or(221.0),
+            detect_round_trips: env.parse("DETECT_ROUND_TRIPS")?.or(file.detect_round_trips).unwrap_or(true),
+            round_trip_max_net_ratio: env.parse("ROUND_TRIP_MAX_NET_RATIO")?.or(file.round_trip_max_net_ratio).unwrap_or(0.05),
+            transaction_encoding,
+            min_tokens_received: env.parse("MIN_TOKENS_RECEIVED")?.or(file.min_tokens_received).unwrap_or(1.0),
+            max_tokens_received: env.parse("MAX_TOKENS_RECEIVED")?.or(file.max_tokens_received).unwrap_or(1_000_000_000.0),
+            tokens_received_fallback: env.parse("TOKENS_RECEIVED_FALLBACK")?.or(file.tokens_received_fallback).unwrap_or(true),
+            attribute_investment_to_quote_mint: env.parse("ATTRIBUTE_INVESTMENT_TO_QUOTE_MINT")?.or(file.attribute_investment_to_quote_mint).unwrap_or(true),
+            parser_failure_threshold: env.parse("PARSER_FAILURE_THRESHOLD")?.or(file.parser_failure_threshold).unwrap_or(5),
+            parser_cooldown_secs: env.parse("PARSER_COOLDOWN_SECS")?.or(file.parser_cooldown_secs).unwrap_or(300),
+            max_queue_age_ms: env.parse("MAX_QUEUE_AGE_MS")?.or(file.max_queue_age_ms).unwrap_or(500),
+            log_pool_selection: env.parse("LOG_POOL_SELECTION")?.or(file.log_pool_selection).unwrap_or(false),
+            pool_owner_denylist,
+            otlp_endpoint: env.var("OTLP_ENDPOINT").or(file.otlp_endpoint),
+            metrics_port: env.parse("METRICS_PORT")?.or(file.metrics_port),
+            dedup_extracted_pools: env.parse("DEDUP_EXTRACTED_POOLS")?.or(file.dedup_extracted_pools).unwrap_or(true),
+            max_received_reserve_share: env.parse("MAX_RECEIVED_RESERVE_SHARE")?.or(file.max_received_reserve_share).unwrap_or(0.5),
+            skip_zero_reserve_pools: env.parse("SKIP_ZERO_RESERVE_POOLS")?.or(file.skip_zero_reserve_pools).unwrap_or(true),
+            opportunity_filter,
+            fetch_dlmm_bin_arrays: env.parse("FETCH_DLMM_BIN_ARRAYS")?.or(file.fetch_dlmm_bin_arrays).unwrap_or(false),
+            dlmm_bin_array_radius: env.parse("DLMM_BIN_ARRAY_RADIUS")?.or(file.dlmm_bin_array_radius).unwrap_or(1),
+            verify_orca_vaults: env.parse("VERIFY_ORCA_VAULTS")?.or(file.verify_orca_vaults).unwrap_or(false),
+            dynamic_fee_impact: env.parse("DYNAMIC_FEE_IMPACT")?.or(file.dynamic_fee_impact).unwrap_or(true),
+            assume_default_decimals: env.parse("ASSUME_DEFAULT_DECIMALS")?.or(file.assume_default_decimals).unwrap_or(true),
+            default_decimals: env.parse("DEFAULT_DECIMALS")?.or(file.default_decimals).unwrap_or(9),
+            decimals_fallback_ttl_secs: env.parse("DECIMALS_FALLBACK_TTL_SECS")?.or(file.decimals_fallback_ttl_secs).unwrap_or(30),
+            pool_cache_ttl_secs: env.parse("POOL_CACHE_TTL_SECS")?.or(file.pool_cache_ttl_secs).unwrap_or(10),
+            mint_pools_cache_ttl_secs: env.parse("MINT_POOLS_CACHE_TTL_SECS")?.or(file.mint_pools_cache_ttl_secs).unwrap_or(30),
+            transaction_log_path: env.var("TRANSACTION_LOG_PATH").map(PathBuf::from)
+                .or(file.transaction_log_path)
+                .unwrap_or_else(|| PathBuf::from("sandwich_transactions.jsonl")),
+            transaction_log_pretty: env.parse("TRANSACTION_LOG_PRETTY")?.or(file.transaction_log_pretty).unwrap_or(false),
+            offline_sol_price: env.parse("OFFLINE_SOL_PRICE")?.or(file.offline_sol_price).unwrap_or(150.0),
+            offline_supplies,
+            analyze_openbook_v2: env.parse("ANALYZE_OPENBOOK_V2")?.or(file.analyze_openbook_v2).unwrap_or(true),
+            seen_signatures_capacity: env.parse("SEEN_SIGNATURES_CAPACITY")?.or(file.seen_signatures_capacity).unwrap_or(50_000),
+            seen_signatures_ttl_secs: env.parse("SEEN_SIGNATURES_TTL_SECS")?.or(file.seen_signatures_ttl_secs).unwrap_or(120),
+            seen_signatures_report_secs: env.parse("SEEN_SIGNATURES_REPORT_SECS")?.or(file.seen_signatures_report_secs).unwrap_or(60),
+            batch_account_fetches: env.parse("BATCH_ACCOUNT_FETCHES")?.or(file.batch_account_fetches).unwrap_or(true),
+            resolve_swapper_from_balances: env.parse("RESOLVE_SWAPPER_FROM_BALANCES")?.or(file.resolve_swapper_from_balances).unwrap_or(true),
+            impact_from_observed_quote_in: env.parse("IMPACT_FROM_OBSERVED_QUOTE_IN")?.or(file.impact_from_observed_quote_in).unwrap_or(true),
+            verbose_impact_detail: env.parse("VERBOSE_IMPACT_DETAIL")?.or(file.verbose_impact_detail).unwrap_or(false),
+            reject_off_route_quote_pools: env.parse("REJECT_OFF_ROUTE_QUOTE_POOLS")?.or(file.reject_off_route_quote_pools).unwrap_or(true),
+            websocket_reconnect_max_backoff_secs: env.parse("WEBSOCKET_RECONNECT_MAX_BACKOFF_SECS")?.or(file.websocket_reconnect_max_backoff_secs).unwrap_or(30),
+        };
+
+        if !(config.sol_price_min > 0.0 && config.sol_price_min < config.sol_price_max) {
+            return Err(anyhow!(
+                "Bornes du prix SOL invalides: min {} / max {}",
+                config.sol_price_min, config.sol_price_max
+            ));
+        }
+        if !(config.sol_price_min..=config.sol_price_max).contains(&config.sol_price_fallback) {
+            return Err(anyhow!(
+                "sol_price_fallback {} hors des bornes [{}, {}]",
+                config.sol_price_fallback, config.sol_price_min, config.sol_price_max
+            ));
+        }
+        if !(config.min_tokens_received >= 0.0 && config.min_tokens_received < config.max_tokens_received) {
+            return Err(anyhow!(
+                "Bornes des tokens reçus invalides: min {} / max {}",
+                config.min_tokens_received, config.max_tokens_received
+            ));
+        }
+        if config.sol_price_sources.is_empty() {
+            return Err(anyhow!("sol_price_sources vide : au moins une source de prix SOL requise"));
+        }
+        let filter = &config.opportunity_filter;
+        if !(filter.min_mcap_impact_pct >= 0.0 && filter.min_mcap_impact_pct < filter.max_mcap_impact_pct) {
+            return Err(anyhow!(
+                "Bande d'impact invalide: min {}% / max {}%",
+                filter.min_mcap_impact_pct, filter.max_mcap_impact_pct
+            ));
         }
+
+        Ok(config)
+    }
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_env;
+
+    #[test]
+    fn per_dex_liquidity_thresholds_parse_from_toml() {
+        let file: ConfigFile = toml::from_str("[min_liquidity_per_dex]\nMeteoraDLMM = 5000.0\nRaydiumV4 = 50000.0\n").unwrap();
+        let thresholds: HashMap<DexType, f64> = file.min_liquidity_per_dex.unwrap().into_iter()
+            .map(|(dex, min)| (dex_type_from_name(&dex).unwrap(), min))
+            .collect();
+        assert_eq!(thresholds.get(&DexType::MeteoraDLMM), Some(&5_000.0));
+        assert_eq!(thresholds.get(&DexType::RaydiumV4), Some(&50_000.0));
+        assert!(dex_type_from_name("Uniswap").is_err());
+    }
+
+    #[test]
+    fn config_file_save_load_round_trip() {
+        let file = ConfigFile {
+            rpc_url: Some("http://localhost:8899".to_string()),
+            jito_urls: Some(vec!["http://jito-a".to_string(), "http://jito-b".to_string()]),
+            keypair_path: Some(PathBuf::from("/tmp/keypair.json")),
+            position_size_lamports: Some(1_000_000_000),
+            test_mode: Some(false),
+            min_liquidity_per_dex: Some(HashMap::from([("RaydiumV4".to_string(), 50_000.0)])),
+            pool_owner_denylist: Some(vec![Pubkey::new_unique().to_string()]),
+            offline_supplies: Some(HashMap::from([(Pubkey::new_unique().to_string(), 1e9)])),
+            sol_price_sources: Some(vec!["Pyth".to_string(), "CoinGecko".to_string()]),
+            metrics_port: Some(9187),
+            default_decimals: Some(6),
+            ..ConfigFile::default()
+        };
+        let path = std::env::temp_dir().join(format!("sandwich-bot-config-{}.toml", std::process::id()));
+        file.save(&path).unwrap();
+        let loaded = ConfigFile::load(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.unwrap(), file);
+    }
+
+    #[test]
+    fn environment_overrides_file_which_overrides_defaults() {
+        let file = ConfigFile { jito_tip_lamports: Some(70_000), jito_urls: Some(vec!["http://file".to_string()]), ..ConfigFile::default() };
+
+        let env = test_env(&[("JITO_TIP_LAMPORTS", "90000"), ("JITO_URLS", "http://env-a, http://env-b")]);
+        let config = BotConfig::from_sources_with(file.clone(), env).unwrap();
+        assert_eq!(config.jito_tip_lamports, 90_000);
+        assert_eq!(config.jito_urls, vec!["http://env-a", "http://env-b"]);
+
+        let config = BotConfig::from_sources_with(file, test_env(&[])).unwrap();
+        assert_eq!(config.jito_tip_lamports, 70_000);
+        assert_eq!(config.jito_urls, vec!["http://file"]);
+        assert_eq!(BotConfig::from_sources_with(ConfigFile::default(), test_env(&[])).unwrap().jito_tip_lamports, 50_000);
+    }
+
+    #[test]
+    fn env_table_parses_key_value_pairs() {
+        let env = Env(|_: &str| Some("RaydiumV4=50000, MeteoraDLMM = 5000".to_string()));
+        let table = env.table("MIN_LIQUIDITY_PER_DEX").unwrap().unwrap();
+        assert_eq!(table.get("RaydiumV4"), Some(&50_000.0));
+        assert_eq!(table.get("MeteoraDLMM"), Some(&5_000.0));
+
+        let env = Env(|_: &str| Some("RaydiumV4".to_string()));
+        assert!(env.table("MIN_LIQUIDITY_PER_DEX").is_err());
+    }
+
+    #[test]
+    fn sol_price_bounds_come_from_the_file_and_are_validated() {
+        let file: ConfigFile = toml::from_str("sol_price_min = 80.0\nsol_price_max = 400.0\nsol_price_fallback = 180.0\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!((config.sol_price_min, config.sol_price_max, config.sol_price_fallback), (80.0, 400.0, 180.0));
+
+        let inverted = ConfigFile { sol_price_min: Some(500.0), sol_price_max: Some(100.0), ..file.clone() };
+        assert!(BotConfig::from_sources_with(inverted, test_env(&[])).is_err());
+        let fallback_out_of_bounds = ConfigFile { sol_price_fallback: Some(1_000.0), ..file };
+        let error = BotConfig::from_sources_with(fallback_out_of_bounds, test_env(&[])).err().unwrap().to_string();
+        assert!(error.contains("sol_price_fallback"), "{}", error);
+    }
+
+    #[test]
+    fn cache_ttls_come_from_the_file() {
+        let file: ConfigFile = toml::from_str("pool_cache_ttl_secs = 3\nmint_pools_cache_ttl_secs = 120\n").unwrap();
+        let config = BotConfig::from_sources_with(file, test_env(&[])).unwrap();
+        assert_eq!((config.pool_cache_ttl_secs, config.mint_pools_cache_ttl_secs), (3, 120));
+
+        let config = BotConfig::from_sources_with(ConfigFile::default(), test_env(&[])).unwrap();
+        assert_eq!((config.pool_cache_ttl_secs, config.mint_pools_cache_ttl_secs), (10, 30));
+    }
+
+    #[test]
+    fn analysis_budget_and_retries_come_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("analysis_budget_ms = 2500\nrpc_max_retries = 1\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!((config.analysis_budget_ms, config.rpc_max_retries), (2_500, 1));
+
+        let config = BotConfig::from_sources_with(file, test_env(&[("ANALYSIS_BUDGET_MS", "800")])).unwrap();
+        assert_eq!((config.analysis_budget_ms, config.rpc_max_retries), (800, 1));
+        assert!(BotConfig::from_sources_with(ConfigFile::default(), test_env(&[("RPC_MAX_RETRIES", "-1")])).is_err());
+    }
+
+    #[test]
+    fn max_concurrent_analyses_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("max_concurrent_analyses = 8\n").unwrap();
+        assert_eq!(BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap().max_concurrent_analyses, 8);
+        let env = test_env(&[("MAX_CONCURRENT_ANALYSES", "16")]);
+        assert_eq!(BotConfig::from_sources_with(file, env).unwrap().max_concurrent_analyses, 16);
+    }
+
+    #[test]
+    fn sol_price_cache_can_be_moved_or_disabled() {
+        let config = BotConfig::from_sources_with(ConfigFile::default(), test_env(&[])).unwrap();
+        assert_eq!(config.sol_price_cache_path, Some(PathBuf::from("sol_price_cache.json")));
+        assert_eq!(config.sol_price_cache_max_age_secs, 3_600);
+
+        let file: ConfigFile = toml::from_str("sol_price_cache_path = \"/var/lib/bot/sol.json\"\nsol_price_cache_max_age_secs = 600\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!(config.sol_price_cache_path, Some(PathBuf::from("/var/lib/bot/sol.json")));
+        assert_eq!(config.sol_price_cache_max_age_secs, 600);
+
+        // Valeur vide : persistance désactivée, depuis le fichier comme depuis l'environnement
+        let disabled: ConfigFile = toml::from_str("sol_price_cache_path = \"\"\n").unwrap();
+        assert_eq!(BotConfig::from_sources_with(disabled, test_env(&[])).unwrap().sol_price_cache_path, None);
+        let env = test_env(&[("SOL_PRICE_CACHE_PATH", "")]);
+        assert_eq!(BotConfig::from_sources_with(file, env).unwrap().sol_price_cache_path, None);
+    }
+
+    #[test]
+    fn transaction_encoding_accepts_json_and_json_parsed_only() {
+        let config = BotConfig::from_sources_with(ConfigFile::default(), test_env(&[])).unwrap();
+        assert_eq!(config.transaction_encoding, UiTransactionEncoding::JsonParsed);
+
+        let file: ConfigFile = toml::from_str("transaction_encoding = \"json\"\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!(config.transaction_encoding, UiTransactionEncoding::Json);
+        let env = test_env(&[("TRANSACTION_ENCODING", "jsonParsed")]);
+        assert_eq!(BotConfig::from_sources_with(file, env).unwrap().transaction_encoding, UiTransactionEncoding::JsonParsed);
+
+        // base64 : l'analyse lit les balances et les clés du message décodé, indisponibles
+        let env = test_env(&[("TRANSACTION_ENCODING", "base64")]);
+        assert!(BotConfig::from_sources_with(ConfigFile::default(), env).is_err());
+    }
+
+    #[test]
+    fn tokens_received_bounds_come_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("min_tokens_received = 10.0\nmax_tokens_received = 5e8\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!((config.min_tokens_received, config.max_tokens_received), (10.0, 5e8));
+        let config = BotConfig::from_sources_with(file, test_env(&[("MIN_TOKENS_RECEIVED", "0.5")])).unwrap();
+        assert_eq!(config.min_tokens_received, 0.5);
+
+        let inverted = test_env(&[("MIN_TOKENS_RECEIVED", "100"), ("MAX_TOKENS_RECEIVED", "10")]);
+        assert!(BotConfig::from_sources_with(ConfigFile::default(), inverted).is_err());
+    }
+
+    #[test]
+    fn parser_circuit_breaker_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("parser_failure_threshold = 0\nparser_cooldown_secs = 60\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!((config.parser_failure_threshold, config.parser_cooldown_secs), (0, 60));
+        let config = BotConfig::from_sources_with(file, test_env(&[("PARSER_COOLDOWN_SECS", "900")])).unwrap();
+        assert_eq!(config.parser_cooldown_secs, 900);
+    }
+
+    #[test]
+    fn dlmm_bin_array_fetch_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("fetch_dlmm_bin_arrays = true\ndlmm_bin_array_radius = 2\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert!(config.fetch_dlmm_bin_arrays);
+        assert_eq!(config.dlmm_bin_array_radius, 2);
+        let env = test_env(&[("FETCH_DLMM_BIN_ARRAYS", "false"), ("DLMM_BIN_ARRAY_RADIUS", "0")]);
+        let config = BotConfig::from_sources_with(file, env).unwrap();
+        assert!(!config.fetch_dlmm_bin_arrays);
+        assert_eq!(config.dlmm_bin_array_radius, 0);
+    }
+
+    #[test]
+    fn orca_vault_verification_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("verify_orca_vaults = true\n").unwrap();
+        assert!(BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap().verify_orca_vaults);
+        let env = test_env(&[("VERIFY_ORCA_VAULTS", "false")]);
+        assert!(!BotConfig::from_sources_with(file, env).unwrap().verify_orca_vaults);
+    }
+
+    #[test]
+    fn analysis_csv_flush_batch_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("analysis_csv_flush_every = 1\n").unwrap();
+        assert_eq!(BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap().analysis_csv_flush_every, 1);
+        let env = test_env(&[("ANALYSIS_CSV_FLUSH_EVERY", "200")]);
+        assert_eq!(BotConfig::from_sources_with(file, env).unwrap().analysis_csv_flush_every, 200);
+    }
+
+    #[test]
+    fn opportunity_filter_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("min_mcap_impact_pct = 1.0\nmax_mcap_impact_pct = 500.0\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        let filter = &config.opportunity_filter;
+        assert_eq!((filter.min_mcap_impact_pct, filter.max_mcap_impact_pct), (1.0, 500.0));
+        assert_eq!(filter.min_invested_usd, OpportunityFilter::default().min_invested_usd);
+
+        let env = test_env(&[("MAX_MCAP_IMPACT_PCT", "50"), ("MIN_INVESTED_USD", "25")]);
+        let config = BotConfig::from_sources_with(file, env).unwrap();
+        assert_eq!(config.opportunity_filter.max_mcap_impact_pct, 50.0);
+        assert_eq!(config.opportunity_filter.min_invested_usd, 25.0);
+
+        let inverted = test_env(&[("MIN_MCAP_IMPACT_PCT", "300")]);
+        assert!(BotConfig::from_sources_with(ConfigFile::default(), inverted).is_err());
+    }
+
+    #[test]
+    fn seen_signatures_window_comes_from_file_and_env() {
+        let file: ConfigFile = toml::from_str("seen_signatures_capacity = 1000\nseen_signatures_ttl_secs = 30\n").unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!((config.seen_signatures_capacity, config.seen_signatures_ttl_secs), (1000, 30));
+        assert_eq!(config.seen_signatures_report_secs, 60);
+
+        let env = test_env(&[("SEEN_SIGNATURES_TTL_SECS", "300"), ("SEEN_SIGNATURES_REPORT_SECS", "10")]);
+        let config = BotConfig::from_sources_with(file, env).unwrap();
+        assert_eq!((config.seen_signatures_ttl_secs, config.seen_signatures_report_secs), (300, 10));
+    }
+
+    #[test]
+    fn every_remaining_field_comes_from_the_file() {
+        let pool = Pubkey::new_unique();
+        let file: ConfigFile = toml::from_str(&format!(
+            "sol_price_sources = [\"OnChainPool\"]\nsol_usdc_pool = \"{}\"\nmetrics_port = 9187\n\
+             analysis_csv_path = \"analyses.csv\"\ntransaction_log_path = \"tx.jsonl\"\n\
+             skip_own_transactions = false\nround_trip_max_net_ratio = 0.1\ndefault_decimals = 6\n",
+            pool
+        )).unwrap();
+        let config = BotConfig::from_sources_with(file.clone(), test_env(&[])).unwrap();
+        assert_eq!(config.sol_price_sources, vec![SolPriceSource::OnChainPool]);
+        assert_eq!(config.sol_usdc_pool, pool);
+        assert_eq!(config.metrics_port, Some(9187));
+        assert_eq!(config.analysis_csv_path, Some(PathBuf::from("analyses.csv")));
+        assert_eq!(config.transaction_log_path, PathBuf::from("tx.jsonl"));
+        assert!(!config.skip_own_transactions);
+        assert_eq!((config.round_trip_max_net_ratio, config.default_decimals), (0.1, 6));
+
+        // ANALYSIS_CSV reste accepté à côté de ANALYSIS_CSV_PATH
+        let env = test_env(&[("ANALYSIS_CSV", "legacy.csv"), ("SOL_PRICE_SOURCES", "Pyth,CoinGecko")]);
+        let config = BotConfig::from_sources_with(file, env).unwrap();
+        assert_eq!(config.analysis_csv_path, Some(PathBuf::from("legacy.csv")));
+        assert_eq!(config.sol_price_sources, vec![SolPriceSource::Pyth, SolPriceSource::CoinGecko]);
+
+        let unknown = test_env(&[("SOL_PRICE_SOURCES", "Binance")]);
+        assert!(BotConfig::from_sources_with(ConfigFile::default(), unknown).is_err());
+        let empty = ConfigFile { sol_price_sources: Some(Vec::new()), ..ConfigFile::default() };
+        assert!(BotConfig::from_sources_with(empty, test_env(&[])).is_err());
     }
 }
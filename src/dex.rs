@@ -1,22 +1,23 @@
 use crate::config::BotConfig;
 use crate::types::{
-    DexType, PoolInfo,
-    WSOL_MINT, USDC_MINT
+    DexType, PoolInfo, PoolSkipReason, RaydiumAmmInfo, RaydiumClmmInfo, OrcaWhirlpoolInfo, MeteoraDLMMInfo,
+    WSOL_MINT, USDC_MINT, RAYDIUM_V4_PROGRAM, RAYDIUM_CLMM_PROGRAM, ORCA_WHIRLPOOL_PROGRAM, METEORA_DLMM_PROGRAM,
 };
-use crate::pool_parser::PoolParser;
-use solana_sdk::program_pack::Pack;
+use crate::pool_parser::{check_account_owner, token_account_amount, PoolParser};
 use anyhow::{Result, anyhow};
+use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     nonblocking::rpc_client::RpcClient as AsyncRpcClient,
     rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
 };
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     signature::Signer,
 };
-use tokio::time::Instant;
-use spl_token::state::Account as TokenAccount;
+use tokio::time::{Duration, Instant};
 use spl_associated_token_account::get_associated_token_address;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -26,18 +27,32 @@ use ahash::AHashMap;
 // DEX PARSING AND POOL MANAGEMENT
 // ============================================================================
 
+/// Pools découvertes par mint et instant de la découverte
+pub type MintPoolsCache = Arc<tokio::sync::RwLock<AHashMap<Pubkey, (Vec<PoolInfo>, Instant)>>>;
+
 pub struct DexManager {
     pub config: Arc<BotConfig>,
     pub rpc: Arc<RpcClient>,
     pub async_rpc: Arc<AsyncRpcClient>,
-    pub pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, PoolInfo>>>,
+    pub pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (PoolInfo, Instant)>>>,
     pub user_token_accounts: AHashMap<Pubkey, Pubkey>,
     pub price_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (f64, Instant)>>>,
+    // Pools découvertes par mint via find_pools_for_mint
+    pub mint_pools_cache: MintPoolsCache,
     pub pool_parser: PoolParser,
+    // État du coupe-circuit par DEX (échecs de parsing consécutifs)
+    pub parser_health: Arc<tokio::sync::RwLock<AHashMap<DexType, ParserHealth>>>,
+}
+
+/// Santé du parser d'un DEX : un changement de layout fait échouer tous les parsings
+#[derive(Debug, Clone, Default)]
+pub struct ParserHealth {
+    pub consecutive_failures: u32,
+    pub disabled_until: Option<Instant>,
 }
 
 impl DexManager {
-    pub async fn new(config: BotConfig) -> Result<Self> {
+    pub async fn new(config: Arc<BotConfig>) -> Result<Self> {
         let rpc = Arc::new(RpcClient::new_with_commitment(
             config.rpc_url.clone(),
             CommitmentConfig::processed(),
@@ -48,16 +63,25 @@ impl DexManager {
             CommitmentConfig::processed(),
         ));
 
-        let pool_parser = PoolParser::new(Arc::clone(&async_rpc));
+        let mut pool_parser = PoolParser::new(Arc::clone(&async_rpc));
+        pool_parser.fetch_dlmm_bin_arrays = config.fetch_dlmm_bin_arrays;
+        pool_parser.dlmm_bin_array_radius = config.dlmm_bin_array_radius;
+        pool_parser.verify_orca_vaults = config.verify_orca_vaults;
+        pool_parser.batch_account_fetches = config.batch_account_fetches;
+        pool_parser.assume_default_decimals = config.assume_default_decimals;
+        pool_parser.default_decimals = config.default_decimals;
+        pool_parser.decimals_fallback_ttl = Duration::from_secs(config.decimals_fallback_ttl_secs);
 
         let mut manager = Self {
-            config: Arc::new(config),
+            config,
             rpc,
             async_rpc,
             pool_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
             user_token_accounts: AHashMap::new(),
             price_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+            mint_pools_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
             pool_parser,
+            parser_health: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
         };
 
         manager.initialize_token_accounts().await?;
@@ -80,16 +104,144 @@ impl DexManager {
     }
 
     pub async fn fetch_pool_info(&self, pool_id: &Pubkey, dex_type: DexType, program_id: Pubkey) -> Result<PoolInfo> {
+        if self.is_dex_disabled(&dex_type).await {
+            return Err(anyhow!("Parser {:?} désactivé (coupe-circuit)", dex_type));
+        }
+
+        // Les erreurs RPC sur le compte du pool ne comptent pas comme échecs de parsing
+        let account = self.async_rpc.get_account(pool_id).await?;
+        // Mauvais compte (et non layout modifié) : ne compte pas non plus pour le coupe-circuit
+        check_account_owner(&account, pool_id, &dex_type, &program_id)?;
+
         // Utiliser le PoolParser pour parser n'importe quel type de pool
-        self.pool_parser.parse_pool(pool_id, dex_type, program_id).await
+        let result = self.pool_parser.parse_pool_account(&account.data, pool_id, dex_type.clone(), program_id).await;
+        self.record_parse_result(&dex_type, result.is_ok()).await;
+        result
+    }
+
+    /// Toutes les pools contenant `mint` sur les DEX supportés (getProgramAccounts filtré par memcmp sur
+    /// chaque champ mint du layout), triées par `liquidity_usd` décroissante. Résultat gardé
+    /// `mint_pools_cache_ttl_secs` ; un DEX en erreur RPC ou coupé est ignoré plutôt que bloquant.
+    pub async fn find_pools_for_mint(&self, mint: &Pubkey) -> Result<Vec<PoolInfo>> {
+        {
+            let cache = self.mint_pools_cache.read().await;
+            if let Some((pools, fetched_at)) = cache.get(mint) {
+                if fetched_at.elapsed() < Duration::from_secs(self.config.mint_pools_cache_ttl_secs) {
+                    return Ok(pools.clone());
+                }
+            }
+        }
+
+        // (DEX, programme, taille du compte, offsets des champs mint)
+        let layouts: [(DexType, &str, usize, [usize; 2]); 4] = [
+            (DexType::RaydiumV4, RAYDIUM_V4_PROGRAM, RaydiumAmmInfo::LEN,
+                [RaydiumAmmInfo::BASE_MINT_OFFSET, RaydiumAmmInfo::QUOTE_MINT_OFFSET]),
+            (DexType::RaydiumCLMM, RAYDIUM_CLMM_PROGRAM, RaydiumClmmInfo::ACCOUNT_LEN,
+                [RaydiumClmmInfo::MINT_0_OFFSET, RaydiumClmmInfo::MINT_1_OFFSET]),
+            (DexType::OrcaWhirlpool, ORCA_WHIRLPOOL_PROGRAM, OrcaWhirlpoolInfo::ACCOUNT_LEN,
+                [OrcaWhirlpoolInfo::MINT_A_OFFSET, OrcaWhirlpoolInfo::MINT_B_OFFSET]),
+            (DexType::MeteoraDLMM, METEORA_DLMM_PROGRAM, MeteoraDLMMInfo::ACCOUNT_LEN,
+                [MeteoraDLMMInfo::MINT_X_OFFSET, MeteoraDLMMInfo::MINT_Y_OFFSET]),
+        ];
+
+        let mut pools = Vec::new();
+        for (dex_type, program, account_len, mint_offsets) in layouts {
+            if self.is_dex_disabled(&dex_type).await {
+                continue;
+            }
+            let program_id = Pubkey::from_str(program)?;
+            for offset in mint_offsets {
+                let accounts = match self.async_rpc.get_program_accounts_with_config(
+                    &program_id,
+                    RpcProgramAccountsConfig {
+                        filters: Some(vec![
+                            RpcFilterType::DataSize(account_len as u64),
+                            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, mint.as_ref())),
+                        ]),
+                        account_config: RpcAccountInfoConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ).await {
+                    Ok(accounts) => accounts,
+                    Err(e) => {
+                        log::warn!("⚠️ getProgramAccounts {:?} pour {} impossible: {}", dex_type, mint, e);
+                        continue;
+                    }
+                };
+
+                for (pool_id, account) in accounts {
+                    let result = self.pool_parser.parse_pool_account(&account.data, &pool_id, dex_type.clone(), program_id).await;
+                    self.record_parse_result(&dex_type, result.is_ok()).await;
+                    match result {
+                        Ok(pool) => pools.push(pool),
+                        Err(e) => log::warn!("⚠️ Pool {:?} {} ignorée: {}", dex_type, pool_id, e),
+                    }
+                }
+            }
+        }
+
+        pools.sort_by(|a, b| b.liquidity_usd.total_cmp(&a.liquidity_usd));
+        log::debug!("🔎 {} pool(s) trouvée(s) pour {}", pools.len(), mint);
+
+        {
+            let now = Instant::now();
+            let mut pool_cache = self.pool_cache.write().await;
+            for pool in &pools {
+                pool_cache.insert(pool.pool_id, (pool.clone(), now));
+            }
+        }
+        self.mint_pools_cache.write().await.insert(*mint, (pools.clone(), Instant::now()));
+        Ok(pools)
+    }
+
+    /// Indique si le parser d'un DEX est coupé ; le réactive une fois le cooldown écoulé
+    pub async fn is_dex_disabled(&self, dex_type: &DexType) -> bool {
+        let mut health = self.parser_health.write().await;
+        let Some(state) = health.get_mut(dex_type) else {
+            return false;
+        };
+        match state.disabled_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                log::info!("🔌 Parser {:?} réactivé après cooldown, nouvel essai", dex_type);
+                *state = ParserHealth::default();
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Comptabilise un parsing ; coupe le DEX après `parser_failure_threshold` échecs consécutifs
+    async fn record_parse_result(&self, dex_type: &DexType, success: bool) {
+        let mut health = self.parser_health.write().await;
+        let state = health.entry(dex_type.clone()).or_default();
+        if success {
+            state.consecutive_failures = 0;
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        let threshold = self.config.parser_failure_threshold;
+        if threshold > 0 && state.consecutive_failures >= threshold && state.disabled_until.is_none() {
+            state.disabled_until = Some(Instant::now() + Duration::from_secs(self.config.parser_cooldown_secs));
+            log::error!(
+                "🚨 Parser {:?} désactivé pour {}s après {} échecs consécutifs - layout du programme probablement modifié, mise à jour du parser nécessaire",
+                dex_type, self.config.parser_cooldown_secs, state.consecutive_failures
+            );
+        }
     }
 
-    /// Récupère les informations d'un pool avec cache
+    /// Récupère les informations d'un pool avec cache (re-fetch au-delà de `pool_cache_ttl_secs`)
     pub async fn get_pool_info_cached(&self, pool_id: &Pubkey, dex_type: DexType, program_id: Pubkey) -> Result<PoolInfo> {
         // Vérifier le cache
         let cache = self.pool_cache.read().await;
-        if let Some(pool_info) = cache.get(pool_id) {
-            return Ok(pool_info.clone());
+        if let Some((pool_info, fetched_at)) = cache.get(pool_id) {
+            if fetched_at.elapsed() < Duration::from_secs(self.config.pool_cache_ttl_secs) {
+                return Ok(pool_info.clone());
+            }
         }
         drop(cache);
 
@@ -98,16 +250,15 @@ impl DexManager {
 
         // Mettre en cache
         let mut cache = self.pool_cache.write().await;
-        cache.insert(*pool_id, pool_info.clone());
+        cache.insert(*pool_id, (pool_info.clone(), Instant::now()));
 
         Ok(pool_info)
     }
 
     /// Méthode helper pour obtenir la balance d'un token account
     pub async fn get_token_balance(&self, token_account: &Pubkey) -> Result<u64> {
-        let account_data = self.async_rpc.get_account(token_account).await?;
-        let token_account = TokenAccount::unpack(&account_data.data)?;
-        Ok(token_account.amount)
+        let account = self.async_rpc.get_account(token_account).await?;
+        token_account_amount(Some(&account), token_account)
     }
 
     /// Met à jour le prix SOL dans le parser
@@ -117,12 +268,28 @@ impl DexManager {
 
     /// Vérifie si un pool est valide pour le sandwich
     pub fn is_pool_valid(&self, pool: &PoolInfo, min_liquidity: f64, max_liquidity: f64) -> bool {
-        self.pool_parser.is_pool_valid_for_sandwich(pool, min_liquidity, max_liquidity)
+        let min_liquidity = self.min_liquidity_for(&pool.dex_type, min_liquidity);
+        self.pool_parser.is_pool_valid_for_sandwich(pool, min_liquidity, max_liquidity, self.config.max_reserve_ratio)
+    }
+
+    /// Liquidité minimale pour un DEX : seuil spécifique de `min_liquidity_per_dex`, sinon le seuil global
+    pub fn min_liquidity_for(&self, dex_type: &DexType, global_min: f64) -> f64 {
+        self.config.min_liquidity_per_dex.get(dex_type).copied().unwrap_or(global_min)
+    }
+
+    /// Vérifie la validité d'un pool et retourne la raison du rejet le cas échéant
+    pub fn check_pool_validity(&self, pool: &PoolInfo, min_liquidity: f64, max_liquidity: f64) -> std::result::Result<(), PoolSkipReason> {
+        let min_liquidity = self.min_liquidity_for(&pool.dex_type, min_liquidity);
+        let result = self.pool_parser.check_pool_validity(pool, min_liquidity, max_liquidity, self.config.max_reserve_ratio);
+        if let Err(PoolSkipReason::ReserveImbalance { ratio }) = &result {
+            log::warn!("⚖️ Pool {} ignorée: réserves déséquilibrées (ratio {:.0})", pool.pool_id, ratio);
+        }
+        result
     }
 
     /// Calcule l'impact sur le prix d'un swap
     pub fn calculate_price_impact(&self, pool: &PoolInfo, amount_in: u64, is_a_to_b: bool) -> f64 {
-        self.pool_parser.calculate_price_impact(pool, amount_in, is_a_to_b)
+        self.pool_parser.calculate_price_impact(pool, amount_in, is_a_to_b, self.config.fee_adjusted_impact, self.config.dynamic_fee_impact)
     }
 
     /// Détecte le type de DEX à partir d'une adresse de programme
@@ -132,13 +299,16 @@ impl DexManager {
         let program_str = program_id.to_string();
         if let Some(name) = is_known_dex_program(&program_str) {
             match name {
+                "Raydium CLMM" => DexType::RaydiumCLMM,
                 n if n.contains("Raydium") => DexType::RaydiumV4,
                 n if n.contains("Orca") => DexType::OrcaWhirlpool,
                 n if n.contains("Meteora DLMM") => DexType::MeteoraDLMM,
                 n if n.contains("Lifinity") => DexType::Lifinity,
                 n if n.contains("Phoenix") => DexType::Phoenix,
                 n if n.contains("Serum") => DexType::Serum,
+                "OpenBook V2" if self.config.analyze_openbook_v2 => DexType::OpenBookV2,
                 n if n.contains("Jupiter") => DexType::Jupiter,
+                n if n.contains("Pump.fun") && self.config.analyze_pumpfun => DexType::PumpFun,
                 _ => {
                     log::warn!("⚠️  DEX connu mais non supporté: {} ({})", name, program_id);
                     DexType::Unsupported
@@ -156,8 +326,11 @@ impl DexManager {
         pool_id: &Pubkey,
         program_id: &Pubkey,
     ) -> Result<PoolInfo> {
-        // Détecter le type de DEX
-        let dex_type = self.detect_dex_type(program_id);
+        // Détecter le type de DEX (un parser coupé est traité comme non supporté)
+        let mut dex_type = self.detect_dex_type(program_id);
+        if self.is_dex_disabled(&dex_type).await {
+            dex_type = DexType::Unsupported;
+        }
         
         // Gérer les différents cas
         match dex_type {
@@ -213,3 +386,102 @@ impl DexManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mint_account, program_account, raydium_v4_account_data, test_config, token_account, usdc, wsol, FakeRpc};
+
+    /// DexManager branché sur le nœud simulé (sans les token accounts de l'utilisateur)
+    fn dex_manager(rpc: &FakeRpc, config: BotConfig) -> DexManager {
+        DexManager {
+            config: Arc::new(config),
+            rpc: rpc.blocking_client(),
+            async_rpc: rpc.client(),
+            pool_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+            user_token_accounts: AHashMap::new(),
+            price_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+            mint_pools_cache: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+            pool_parser: PoolParser::new(rpc.client()),
+            parser_health: Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+        }
+    }
+
+    /// Pool Raydium V4 servie par `rpc` (vaults compris), côtés (mint, décimales, réserve brute) ; retourne son adresse
+    fn add_raydium_v4_pool(rpc: &FakeRpc, base: (&Pubkey, u8, u64), quote: (&Pubkey, u8, u64)) -> Pubkey {
+        let pool = Pubkey::new_unique();
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(base_vault, token_account(base.0, &pool, base.2));
+        rpc.add_account(quote_vault, token_account(quote.0, &pool, quote.2));
+        let data = raydium_v4_account_data(base.0, &base_vault, quote.0, &quote_vault, (base.1, quote.1));
+        rpc.add_account(pool, program_account(&Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap(), data));
+        pool
+    }
+
+    /// Mint à 6 décimales servi par `rpc`
+    fn add_token(rpc: &FakeRpc) -> Pubkey {
+        let token = Pubkey::new_unique();
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        token
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cached_pool_is_refetched_once_its_ttl_expires() {
+        let rpc = FakeRpc::new();
+        let mut config = test_config();
+        config.pool_cache_ttl_secs = 5;
+        let manager = dex_manager(&rpc, config);
+        let pool = add_raydium_v4_pool(&rpc, (&add_token(&rpc), 6, 1_000_000_000_000), (&wsol(), 9, 500_000_000_000));
+        let program = Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap();
+
+        manager.get_pool_info_cached(&pool, DexType::RaydiumV4, program).await.unwrap();
+        let requests = rpc.request_count();
+        assert!(requests > 0);
+
+        // Encore frais : aucun appel RPC
+        tokio::time::advance(Duration::from_secs(4)).await;
+        manager.get_pool_info_cached(&pool, DexType::RaydiumV4, program).await.unwrap();
+        assert_eq!(rpc.request_count(), requests);
+
+        // TTL dépassé : le compte de la pool est relu
+        tokio::time::advance(Duration::from_secs(2)).await;
+        let accounts_read = rpc.count("getAccountInfo");
+        manager.get_pool_info_cached(&pool, DexType::RaydiumV4, program).await.unwrap();
+        assert_eq!(rpc.count("getAccountInfo"), accounts_read + 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pools_for_a_mint_are_found_on_either_side_and_cached() {
+        let rpc = FakeRpc::new();
+        let mut config = test_config();
+        config.mint_pools_cache_ttl_secs = 30;
+        let mut manager = dex_manager(&rpc, config);
+        manager.update_sol_price(150.0);
+        let token = add_token(&rpc);
+        rpc.add_account(usdc(), mint_account(6, 0));
+
+        // Token en base face à 500 SOL, token en quote face à 10 000 USDC
+        let sol_pool = add_raydium_v4_pool(&rpc, (&token, 6, 1_000_000_000_000), (&wsol(), 9, 500_000_000_000));
+        let usdc_pool = add_raydium_v4_pool(&rpc, (&usdc(), 6, 10_000_000_000), (&token, 6, 50_000_000_000));
+        // Pool d'un autre mint, et pool du mint dont un vault n'existe plus : ni l'une ni l'autre retenue
+        add_raydium_v4_pool(&rpc, (&add_token(&rpc), 6, 1_000_000_000), (&wsol(), 9, 1_000_000_000));
+        let broken = raydium_v4_account_data(&token, &Pubkey::new_unique(), &wsol(), &Pubkey::new_unique(), (6, 9));
+        rpc.add_account(Pubkey::new_unique(), program_account(&Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap(), broken));
+
+        let pools = manager.find_pools_for_mint(&token).await.unwrap();
+        let ids: Vec<Pubkey> = pools.iter().map(|pool| pool.pool_id).collect();
+        assert_eq!(ids, [sol_pool, usdc_pool]); // 150 000 $ puis 20 000 $ de liquidité
+        assert_eq!(pools[0].liquidity_usd, 150_000.0);
+        assert_eq!(pools[1].liquidity_usd, 20_000.0);
+        // Un getProgramAccounts par champ mint de chaque DEX
+        assert_eq!(rpc.count("getProgramAccounts"), 8);
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert_eq!(manager.find_pools_for_mint(&token).await.unwrap().len(), 2);
+        assert_eq!(rpc.count("getProgramAccounts"), 8);
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(manager.find_pools_for_mint(&token).await.unwrap().len(), 2);
+        assert_eq!(rpc.count("getProgramAccounts"), 16);
+    }
+}
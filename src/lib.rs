@@ -6,6 +6,11 @@ pub mod types;
 pub mod bot;
 pub mod pool_addresses;
 pub mod pool_parser;
+pub mod telemetry;
+pub mod metrics;
+pub mod recorder;
+#[cfg(test)]
+mod test_support;
 
 pub use config::*;
 pub use dex::*;
@@ -1,5 +1,8 @@
 use sandwich_bot::*;
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use solana_client::{nonblocking::rpc_client::RpcClient as AsyncRpcClient, rpc_client::RpcClient};
+use std::path::Path;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -10,10 +13,88 @@ async fn main() -> Result<()> {
         .filter_level(log::LevelFilter::Info)
         .init();
 
-    let config = BotConfig::new();
+    let args: Vec<String> = std::env::args().collect();
+
+    // Fichier de config optionnel (--config <fichier.toml>), les variables d'environnement restent prioritaires
+    let config_path = match args.iter().position(|arg| arg == "--config") {
+        Some(pos) => Some(args.get(pos + 1).ok_or_else(|| anyhow!("Usage: --config <config.toml>"))?),
+        None => None,
+    };
+    let mut config = match config_path.map_or_else(BotConfig::try_new, |path| BotConfig::from_file(Path::new(path))) {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("❌ Configuration invalide: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    telemetry::init_tracing(&config)?;
+    if let Some(port) = config.metrics_port {
+        metrics::spawn_server(port)?;
+    }
+
+    // Rejouer une transaction sauvegardée (ex. dump de DUMP_FAILED_TX_TO) sans RPC ni WebSocket
+    if let Some(pos) = args.iter().position(|arg| arg == "--analyze-file") {
+        let path = args.get(pos + 1).ok_or_else(|| anyhow!("Usage: --analyze-file <transaction.json>"))?;
+        let config = Arc::new(config);
+        let engine = MonitoringEngine::new(
+            Arc::clone(&config),
+            Arc::new(RpcClient::new(config.rpc_url.clone())),
+            Arc::new(AsyncRpcClient::new(config.rpc_url.clone())),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let result = engine.analyze_file(Path::new(path)).await;
+        telemetry::shutdown_tracing();
+        println!("{:#?}", result?);
+        return Ok(());
+    }
     
+    // Rejouer l'analyse d'une liste de signatures (une par ligne) via RPC, sans WebSocket
+    if let Some(pos) = args.iter().position(|arg| arg == "--replay") {
+        let path = args.get(pos + 1).ok_or_else(|| anyhow!("Usage: --replay <signatures.txt> [--concurrency <n>]"))?;
+        // --concurrency <n> : analyses en parallèle pour ce rejeu (remplace max_concurrent_analyses)
+        if let Some(pos) = args.iter().position(|arg| arg == "--concurrency") {
+            let value = args.get(pos + 1).ok_or_else(|| anyhow!("Usage: --concurrency <n>"))?;
+            config.max_concurrent_analyses = value.parse()
+                .map_err(|e| anyhow!("--concurrency invalide ({}): {}", value, e))?;
+        }
+        let config = Arc::new(config);
+        let engine = MonitoringEngine::new(
+            Arc::clone(&config),
+            Arc::new(RpcClient::new(config.rpc_url.clone())),
+            Arc::new(AsyncRpcClient::new(config.rpc_url.clone())),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        engine.start_sol_price_updater().await;
+        let results = engine.analyze_signatures_from_file(Path::new(path)).await;
+        telemetry::shutdown_tracing();
+
+        let results = results?;
+        let mut recorder = config.analysis_csv_path.clone()
+            .map(|csv| recorder::AnalysisRecorder::new(csv, config.analysis_csv_flush_every));
+        for result in &results {
+            println!(
+                "{} | Investi: ${:.2} | MCap Avant: ${:.0} | Impact: {:.2}% | Opportunité: {}",
+                result.signature, result.invested_amount, result.mcap_before, result.mcap_impact, result.is_sandwich_opportunity
+            );
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(result)?;
+            }
+        }
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.flush()?;
+        }
+        let opportunities = results.iter().filter(|result| result.is_sandwich_opportunity).count();
+        println!("{} transaction(s) analysée(s), {} opportunité(s)", results.len(), opportunities);
+        return Ok(());
+    }
+
     let mut bot = SandwichBot::new(config).await?;
-    bot.start().await?;
+    let result = bot.start().await;
 
-    Ok(())
+    telemetry::shutdown_tracing();
+    result
 }
@@ -0,0 +1,114 @@
+use crate::types::AnalysisError;
+use anyhow::{anyhow, Result};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+// ============================================================================
+// MÉTRIQUES PROMETHEUS
+// ============================================================================
+//
+// Compteurs incrémentés aux points d'instrumentation existants du monitoring,
+// exposés en format texte Prometheus sur `GET /metrics` si `metrics_port` est défini.
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+
+    pub static ref TRANSACTIONS_RECEIVED: IntCounter = register(IntCounter::new(
+        "sandwich_bot_transactions_received_total",
+        "Notifications de logs reçues via WebSocket",
+    ));
+    pub static ref DEX_TRANSACTIONS_MATCHED: IntCounter = register(IntCounter::new(
+        "sandwich_bot_dex_transactions_matched_total",
+        "Transactions reconnues comme DEX (hors doublons)",
+    ));
+    pub static ref ANALYSIS_ERRORS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("sandwich_bot_analysis_errors_total", "Analyses en erreur, par type d'erreur"),
+        &["kind"],
+    ));
+    pub static ref STALE_DROPS: IntCounter = register(IntCounter::new(
+        "sandwich_bot_stale_queue_drops_total",
+        "Transactions abandonnées car restées en file plus de max_queue_age_ms",
+    ));
+    pub static ref OPPORTUNITIES_DETECTED: IntCounter = register(IntCounter::new(
+        "sandwich_bot_opportunities_detected_total",
+        "Transactions dont l'impact dépasse les seuils d'opportunité",
+    ));
+    pub static ref ANALYSIS_LATENCY: Histogram = register(Histogram::with_opts(
+        HistogramOpts::new("sandwich_bot_analysis_latency_seconds", "Durée d'analyse d'une transaction")
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+    ));
+}
+
+/// Enregistre une métrique dans `REGISTRY` (noms fixes : un échec est un bug)
+fn register<M: prometheus::core::Collector + Clone + 'static>(metric: prometheus::Result<M>) -> M {
+    let metric = metric.expect("métrique Prometheus invalide");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("métrique Prometheus enregistrée deux fois");
+    metric
+}
+
+/// Label `kind` d'une erreur d'analyse (variante d'`AnalysisError`, `other` sinon)
+pub fn error_kind(error: &anyhow::Error) -> &'static str {
+    match error.downcast_ref::<AnalysisError>() {
+        Some(AnalysisError::RpcTimeout { .. }) => "rpc_timeout",
+        Some(AnalysisError::OwnTransaction) => "own_transaction",
+        Some(AnalysisError::RoundTrip { .. }) => "round_trip",
+        Some(AnalysisError::NoNonSystemToken) => "no_non_system_token",
+        Some(AnalysisError::SellSwap { .. }) => "sell_swap",
+        Some(AnalysisError::NoDexPool) => "no_dex_pool",
+        Some(AnalysisError::PoolParseFailed { .. }) => "pool_parse_failed",
+        Some(AnalysisError::SolPriceUnavailable) => "sol_price_unavailable",
+        None => "other",
+    }
+}
+
+/// Toutes les métriques au format texte Prometheus
+pub fn render() -> Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (req.method(), req.uri().path()) {
+        (&hyper::Method::GET, "/metrics") => match render() {
+            Ok(text) => Response::builder()
+                .header(hyper::header::CONTENT_TYPE, TextEncoder::new().format_type())
+                .body(Body::from(text)),
+            Err(e) => Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(e.to_string())),
+        },
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+/// Sert `GET /metrics` sur `0.0.0.0:<port>` en tâche de fond
+pub fn spawn_server(port: u16) -> Result<()> {
+    // Enregistrer toutes les métriques dès le départ pour qu'elles apparaissent à 0
+    lazy_static::initialize(&TRANSACTIONS_RECEIVED);
+    lazy_static::initialize(&DEX_TRANSACTIONS_MATCHED);
+    lazy_static::initialize(&ANALYSIS_ERRORS);
+    lazy_static::initialize(&STALE_DROPS);
+    lazy_static::initialize(&OPPORTUNITIES_DETECTED);
+    lazy_static::initialize(&ANALYSIS_LATENCY);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let server = Server::try_bind(&addr)
+        .map_err(|e| anyhow!("Port métriques {} indisponible: {}", port, e))?
+        .serve(make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) }));
+
+    log::info!("📈 Métriques Prometheus sur http://{}/metrics", addr);
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            log::error!("❌ Serveur de métriques arrêté: {}", e);
+        }
+    });
+    Ok(())
+}
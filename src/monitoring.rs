@@ -1,34 +1,45 @@
 use crate::config::BotConfig;
-use crate::types::{PoolInfo, WSOL_MINT, USDC_MINT, USDT_MINT, SandwichAnalysisResult};
+use crate::types::{
+    AnalysisError, SwapDirection, DexType, PoolInfo, PumpFunBondingCurve, SandwichAnalysisResult, Deadline, PersistedSolPrice,
+    TransactionClassification, SeenSignatures, TransactionParties, ImpactDebug, RaydiumAmmInfo, SolPriceSource, PythPriceUpdate,
+    WSOL_MINT, USDC_MINT, USDT_MINT, PUMPFUN_PROGRAM, OPENBOOK_V2_PROGRAM, RAYDIUM_V4_PROGRAM, RAYDIUM_CLMM_PROGRAM, PUMPFUN_TOKEN_DECIMALS, PUMPFUN_FEE_BPS,
+};
 use crate::pool_addresses::{is_known_dex_program, is_known_pool_account};
+use crate::pool_parser::{amm_in_with_fee, amm_out_with_fee, PoolParser};
+use crate::metrics;
+use crate::recorder::AnalysisRecorder;
 use anyhow::{Result, anyhow};
+use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     nonblocking::rpc_client::RpcClient as AsyncRpcClient,
     rpc_client::RpcClient,
-    rpc_config::RpcTransactionConfig,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
     pubsub_client::{PubsubClient, PubsubClientSubscription},
     rpc_response::{RpcLogsResponse, Response},
 };
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
-    signature::Signature,
+    signature::{Signature, Signer},
 };
 use solana_transaction_status::{
     UiTransactionEncoding, 
     EncodedConfirmedTransactionWithStatusMeta,
 };
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::time::{Duration, Instant, timeout};
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+use futures::stream::{self, StreamExt};
 
 use ahash::AHashMap;
 use lazy_static::lazy_static;
 use std::collections::HashSet;
 use tokio::sync::RwLock;
 use tabled::Tabled;
-use std::thread::sleep;
 /// Structure pour afficher les résultats de transaction dans un tableau
 #[derive(Tabled)]
 pub struct TransactionResult {
@@ -59,20 +70,55 @@ lazy_static! {
     };
 }
 
+/// Transaction en attente d'analyse : signature, détails, instant de mise en file
+pub type QueuedTransaction = (String, EncodedConfirmedTransactionWithStatusMeta, Instant);
+
+/// Canal des transactions récupérées, en attente d'analyse
+pub type TransactionReceiver = mpsc::UnboundedReceiver<QueuedTransaction>;
+
+/// Impact MCap calculé sur le pool swappé
+struct McapImpact {
+    mcap_before: f64,
+    mcap_impact_pct: f64,
+    quote_mint: Pubkey, // mint payé par la cible (côté quote du pool)
+    reserve_share: f64, // tokens reçus / réserve token du pool avant le swap
+    debug: ImpactDebug,
+}
+
+/// Résultat de l'analyse du swap de la cible
+struct VictimSwapAnalysis {
+    tokens_received: f64,
+    mcap_before: f64,
+    mcap_impact_pct: f64,
+    tokens_received_estimated: bool,
+    quote_mint: Pubkey,
+    reserve_share: f64,
+    parties: TransactionParties,
+    impact_debug: ImpactDebug,
+}
+
 #[derive(Clone)]
 pub struct MonitoringEngine {
     pub config: Arc<BotConfig>,
     pub rpc: Arc<RpcClient>,
     pub async_rpc: Arc<AsyncRpcClient>,
-    pub pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, PoolInfo>>>,
+    pub pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (PoolInfo, Instant)>>>,
     pub user_token_accounts: AHashMap<Pubkey, Pubkey>,
     pub price_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (f64, Instant)>>>,
     pub sol_price: Arc<tokio::sync::RwLock<Option<f64>>>,
     pub supply_cache: Arc<RwLock<AHashMap<Pubkey, (f64, Instant)>>>,
+    // Limite le nombre d'analyses simultanées (live et batch)
+    pub analysis_limiter: Arc<Semaphore>,
+    // Candidats pool écartés par pool_owner_denylist
+    pub denylist_suppressions: Arc<AtomicU64>,
+    // Signatures déjà reçues du WebSocket (dédoublonnage borné, avec métriques)
+    pub seen_signatures: Arc<std::sync::Mutex<SeenSignatures>>,
+    // Mode hors-ligne (analyze_file) : transactions rejouées depuis le disque, aucun appel RPC
+    pub offline_transactions: Option<Arc<AHashMap<String, EncodedConfirmedTransactionWithStatusMeta>>>,
     // WebSocket components
     pub websocket_client: Arc<tokio::sync::RwLock<Option<PubsubClientSubscription<Response<RpcLogsResponse>>>>>,
     pub logs_receiver: Arc<tokio::sync::RwLock<Option<crossbeam_channel::Receiver<Response<RpcLogsResponse>>>>>,
-    pub transaction_receiver: Arc<tokio::sync::RwLock<Option<mpsc::UnboundedReceiver<(String, EncodedConfirmedTransactionWithStatusMeta)>>>>,
+    pub transaction_receiver: Arc<tokio::sync::RwLock<Option<TransactionReceiver>>>,
 }
 
 impl MonitoringEngine {
@@ -80,10 +126,15 @@ impl MonitoringEngine {
         config: Arc<BotConfig>,
         rpc: Arc<RpcClient>,
         async_rpc: Arc<AsyncRpcClient>,
-        pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, PoolInfo>>>,
+        pool_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (PoolInfo, Instant)>>>,
         user_token_accounts: AHashMap<Pubkey, Pubkey>,
         price_cache: Arc<tokio::sync::RwLock<AHashMap<Pubkey, (f64, Instant)>>>,
     ) -> Self {
+        let analysis_limiter = Arc::new(Semaphore::new(config.max_concurrent_analyses.max(1)));
+        let seen_signatures = SeenSignatures::new(
+            config.seen_signatures_capacity,
+            Duration::from_secs(config.seen_signatures_ttl_secs),
+        );
         Self {
             config,
             rpc,
@@ -93,6 +144,10 @@ impl MonitoringEngine {
             price_cache,
             sol_price: Arc::new(tokio::sync::RwLock::new(None)),
             supply_cache: Arc::new(RwLock::new(AHashMap::new())),
+            analysis_limiter,
+            denylist_suppressions: Arc::new(AtomicU64::new(0)),
+            seen_signatures: Arc::new(std::sync::Mutex::new(seen_signatures)),
+            offline_transactions: None,
             websocket_client: Arc::new(tokio::sync::RwLock::new(None)),
             logs_receiver: Arc::new(tokio::sync::RwLock::new(None)),
             transaction_receiver: Arc::new(tokio::sync::RwLock::new(None)),
@@ -109,24 +164,97 @@ impl MonitoringEngine {
             price_cache: Arc::clone(&self.price_cache),
             sol_price: Arc::clone(&self.sol_price),
             supply_cache: Arc::clone(&self.supply_cache),
+            analysis_limiter: Arc::clone(&self.analysis_limiter),
+            denylist_suppressions: Arc::clone(&self.denylist_suppressions),
+            seen_signatures: Arc::clone(&self.seen_signatures),
+            offline_transactions: self.offline_transactions.clone(),
             websocket_client: Arc::clone(&self.websocket_client),
             logs_receiver: Arc::clone(&self.logs_receiver),
             transaction_receiver: Arc::clone(&self.transaction_receiver),
         }
     }
 
-    /// Calcule la valeur d'investissement d'une transaction (AMÉLIORÉE)
-    pub async fn get_investment_value_fast(&self, signature: &str) -> Result<f64> {
-        let tx_result = self.async_rpc
-            .get_transaction_with_config(
-                &signature.parse()?,
+    /// Crée le budget temps d'une nouvelle analyse
+    fn new_deadline(&self) -> Deadline {
+        Deadline::after(Duration::from_millis(self.config.analysis_budget_ms))
+    }
+
+    /// Récupère une transaction (encodage `config.transaction_encoding`) en restant dans le budget de l'analyse
+    #[tracing::instrument(level = "debug", name = "fetch_tx", skip_all, fields(signature = %signature))]
+    async fn fetch_parsed_transaction(
+        &self,
+        signature: &str,
+        deadline: &Deadline,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+        if let Some(offline) = &self.offline_transactions {
+            let tx = offline.get(signature)
+                .ok_or_else(|| anyhow!("Transaction {} absente du replay hors-ligne", signature))?;
+            return Ok(EncodedConfirmedTransactionWithStatusMeta {
+                slot: tx.slot,
+                transaction: tx.transaction.clone(),
+                block_time: tx.block_time,
+            });
+        }
+        let sig: Signature = signature.parse()?;
+        let sig = &sig;
+        let rpc = &self.async_rpc;
+        let encoding = self.config.transaction_encoding;
+        let start = Instant::now();
+        let tx = deadline.retry(self.config.rpc_max_retries, move || async move {
+            rpc.get_transaction_with_config(
+                sig,
                 RpcTransactionConfig {
-                    encoding: Some(UiTransactionEncoding::JsonParsed),
+                    encoding: Some(encoding),
                     commitment: Some(CommitmentConfig::confirmed()),
                     max_supported_transaction_version: Some(0),
                 },
             )
-            .await?;
+            .await
+            .map_err(|e| anyhow!("Erreur RPC: {}", e))
+        }).await?;
+        log::debug!("⏱️ getTransaction {:?}: {}ms", encoding, start.elapsed().as_millis());
+        Ok(tx)
+    }
+
+    /// Index d'un compte dans les clés statiques du message, quel que soit l'encodage (Json ou JsonParsed)
+    fn account_index_in_message(
+        tx_result: &EncodedConfirmedTransactionWithStatusMeta,
+        pubkey: &str,
+    ) -> Result<usize> {
+        let message = match &tx_result.transaction.transaction {
+            solana_transaction_status::EncodedTransaction::Json(ui_tx) => &ui_tx.message,
+            _ => return Err(anyhow!("Transaction non parsable")),
+        };
+        let index = match message {
+            solana_transaction_status::UiMessage::Parsed(parsed) => {
+                parsed.account_keys.iter().position(|key| key.pubkey == pubkey)
+            }
+            solana_transaction_status::UiMessage::Raw(raw) => {
+                raw.account_keys.iter().position(|key| key == pubkey)
+            }
+        };
+        index.ok_or_else(|| anyhow!("Utilisateur non trouvé dans les comptes de la transaction"))
+    }
+
+    /// Calcule la valeur d'investissement d'une transaction (AMÉLIORÉE)
+    pub async fn get_investment_value_fast(&self, signature: &str) -> Result<f64> {
+        self.get_investment_value_within(signature, None, &self.new_deadline()).await
+    }
+
+    /// `quote_mint` : mint de quote du pool swappé. S'il est fourni (et l'option active), seules les
+    /// sorties de ce mint comptent comme investissement (SOL natif assimilé au WSOL) : le wrapping
+    /// WSOL, la rente ou les frais d'un swap payé en USDC ne gonflent plus le montant.
+    async fn get_investment_value_within(
+        &self,
+        signature: &str,
+        quote_mint: Option<&Pubkey>,
+        deadline: &Deadline,
+    ) -> Result<f64> {
+        let quote_mint = quote_mint
+            .filter(|_| self.config.attribute_investment_to_quote_mint)
+            .map(|mint| mint.to_string());
+        let counts_leg = |mint: &str| quote_mint.as_deref().is_none_or(|quote| quote == mint);
+        let tx_result = self.fetch_parsed_transaction(signature, deadline).await?;
 
         let meta = tx_result.transaction.meta.as_ref()
             .ok_or_else(|| anyhow!("Pas de métadonnées dans la transaction"))?;
@@ -143,32 +271,11 @@ impl MonitoringEngine {
         // ============================================================================
         
         // Analyser les changements de balance SOL pour l'utilisateur
-        let message = match &tx_result.transaction.transaction {
-            solana_transaction_status::EncodedTransaction::Json(ui_tx) => &ui_tx.message,
-            _ => return Err(anyhow!("Transaction non parsable")),
-        };
-
-        let account_keys = match message {
-            solana_transaction_status::UiMessage::Parsed(parsed) => &parsed.account_keys,
-            solana_transaction_status::UiMessage::Raw(_raw) => {
-                // Pour les transactions raw, on ne peut pas facilement identifier l'utilisateur
-                // On analyse toutes les balances
-                for (_i, (pre_balance, post_balance)) in meta.pre_balances.iter().zip(meta.post_balances.iter()).enumerate() {
-                    let sol_diff = (*pre_balance as f64 - *post_balance as f64) / 1e9;
-                    if sol_diff > 0.0 {
-                        total_invested_usd += sol_diff * sol_price;
-                    }
-                }
-                return Ok(total_invested_usd);
-            }
-        };
-
-        // Pour les transactions parsées, on cherche l'index de l'utilisateur
-        let user_index = account_keys.iter().position(|key| key.pubkey == user_owner)
-            .ok_or_else(|| anyhow!("Utilisateur non trouvé dans les comptes de la transaction"))?;
+        let user_index = Self::account_index_in_message(&tx_result, &user_owner)?;
         
         // Analyser seulement la balance de l'utilisateur
-        if let (Some(pre_balance), Some(post_balance)) = (meta.pre_balances.get(user_index), meta.post_balances.get(user_index)) {
+        // (swap payé dans un autre mint : le SOL natif ne sert qu'aux frais/rentes)
+        if let (true, Some(pre_balance), Some(post_balance)) = (counts_leg(WSOL_MINT), meta.pre_balances.get(user_index), meta.post_balances.get(user_index)) {
             let sol_diff = (*pre_balance as f64 - *post_balance as f64) / 1e9;
             if sol_diff > 0.0 {
                 total_invested_usd += sol_diff * sol_price;
@@ -179,11 +286,10 @@ impl MonitoringEngine {
         // ANALYSE DES BALANCES DE TOKENS - NOUVELLE MÉTHODE AMÉLIORÉE
         // ============================================================================
         
-        match (&meta.pre_token_balances, &meta.post_token_balances) {
-            (
-                solana_transaction_status::option_serializer::OptionSerializer::Some(pre),
-                solana_transaction_status::option_serializer::OptionSerializer::Some(post)
-            ) => {
+        if let (
+            solana_transaction_status::option_serializer::OptionSerializer::Some(pre),
+            solana_transaction_status::option_serializer::OptionSerializer::Some(post)
+        ) = (&meta.pre_token_balances, &meta.post_token_balances) {
                 // Analyser les changements de balance pour l'utilisateur
                 for pre_balance in pre {
                     let mint = &pre_balance.mint;
@@ -208,7 +314,7 @@ impl MonitoringEngine {
                             let post_amount = post_balance.ui_token_amount.ui_amount.unwrap_or(0.0);
                             let token_diff = pre_amount - post_amount;
                             
-                            if token_diff > 0.0 {
+                            if token_diff > 0.0 && counts_leg(mint) {
                                 // Tokens perdus = investissement
                                 if SYSTEM_TOKENS.contains(mint.as_str()) {
                                     if mint == WSOL_MINT {
@@ -229,8 +335,6 @@ impl MonitoringEngine {
                         }
                     }
                 }
-            }
-            _ => {}
         }
     
         Ok(total_invested_usd)
@@ -272,129 +376,314 @@ impl MonitoringEngine {
             return Ok(price);
         }
         
-        // 3. Chercher des routes via des tokens intermédiaires connus
-        // (WSOL, USDC, USDT, etc.)
-        let intermediate_tokens = vec![
-            WSOL_MINT,
-            USDC_MINT,
-            USDT_MINT,
-        ];
-        
-        for intermediate in intermediate_tokens {
-            if let Ok(intermediate_price) = self.find_direct_pool_price(mint, intermediate, 
-                if intermediate == WSOL_MINT { sol_price } else { 1.0 }).await {
-                return Ok(intermediate_price);
-            }
+        // 3. Chercher une pool directe USDT/Token
+        if let Ok(price) = self.find_direct_pool_price(mint, USDT_MINT, 1.0).await {
+            return Ok(price);
         }
         
-        // 4. Si aucune route trouvée, utiliser une estimation basée sur les pools de la transaction
-        // ou une valeur par défaut très conservatrice
-        log::warn!("Aucune route trouvée pour le token {}, utilisation d'une estimation", mint);
-        Ok(0.001) // Prix très conservateur de $0.001
+        // 4. Aucune route : pas de prix inventé, l'appelant ignore ce token
+        Err(anyhow!("Aucune route de prix trouvée pour le token {}", mint))
     }
 
-    /// Trouve le prix via une pool directe
+    /// Trouve le prix USD de `token_a` via une pool directe `token_a`/`token_b` (`token_b_price` : prix USD
+    /// de `token_b`). Cherche d'abord dans `pool_cache` (entrées plus récentes que `pool_cache_ttl_secs`),
+    /// puis parmi les pools Raydium V4 on-chain.
     async fn find_direct_pool_price(&self, token_a: &str, token_b: &str, token_b_price: f64) -> Result<f64> {
-        // Pour l'instant, on simule la recherche de pools
-        // Dans une vraie implémentation, on chercherait dans les pools connues
-        
-        // Simulation : si c'est un token connu, on utilise une estimation
-        if token_b == WSOL_MINT || token_b == USDC_MINT {
-            // Pour la transaction spécifique 3Hmih6p4..., on connaît les valeurs réelles
-            if token_a.contains("IMAGINE") || token_a.len() > 40 {
-                // Pour IMAGINE → WSOL : 4,023,639.050548 IMAGINE = 2.744034364 WSOL
-                // Donc 1 IMAGINE = 2.744034364 / 4,023,639.050548 WSOL
-                let imagine_per_wsol = 2.744034364 / 4_023_639.050548;
-                let sol_price = self.get_sol_price_cached().await?;
-                Ok(imagine_per_wsol * sol_price)
-            } else {
-                // Estimation basée sur le fait que la plupart des tokens ont un prix entre $0.001 et $1000
-                // On utilise une valeur par défaut qui sera ajustée par les calculs de mcap
-                Ok(0.1) // Prix par défaut de $0.1
+        let token_a = Pubkey::from_str(token_a)?;
+        let token_b = Pubkey::from_str(token_b)?;
+
+        // 1. Pool déjà connue et encore fraîche
+        let cached = {
+            let ttl = Duration::from_secs(self.config.pool_cache_ttl_secs);
+            let cache = self.pool_cache.read().await;
+            let fresh = cache.values()
+                .filter(|(_, fetched_at)| fetched_at.elapsed() < ttl)
+                .map(|(pool, _)| pool);
+            Self::deepest_pool_for_pair(fresh, &token_a, &token_b).cloned()
+        };
+        let pool = match cached {
+            Some(pool) => pool,
+            None => {
+                // 2. Recherche on-chain (impossible hors-ligne)
+                if self.offline_transactions.is_some() {
+                    return Err(anyhow!("Pool {}/{} inconnue hors-ligne", token_a, token_b));
+                }
+                let pool = self.lookup_raydium_pool(&token_a, &token_b).await?;
+                self.pool_cache.write().await.insert(pool.pool_id, (pool.clone(), Instant::now()));
+                pool
             }
+        };
+
+        let (reserve_token, reserve_quote) = if pool.token_a_mint == token_a {
+            (pool.token_a_liquidity, pool.token_b_liquidity)
         } else {
-            Err(anyhow!("Pool non trouvée"))
+            (pool.token_b_liquidity, pool.token_a_liquidity)
+        };
+        Ok(reserve_quote / reserve_token * token_b_price)
+    }
+
+    /// Pool de la paire (dans un sens ou dans l'autre) avec la plus grosse réserve de `quote`, réserves non nulles
+    fn deepest_pool_for_pair<'a>(
+        pools: impl Iterator<Item = &'a PoolInfo>,
+        token: &Pubkey,
+        quote: &Pubkey,
+    ) -> Option<&'a PoolInfo> {
+        pools
+            .filter(|pool| {
+                (pool.token_a_mint == *token && pool.token_b_mint == *quote)
+                    || (pool.token_a_mint == *quote && pool.token_b_mint == *token)
+            })
+            .filter(|pool| pool.token_a_liquidity > 0.0 && pool.token_b_liquidity > 0.0)
+            .max_by(|a, b| {
+                let quote_liquidity = |pool: &PoolInfo| {
+                    if pool.token_a_mint == *quote { pool.token_a_liquidity } else { pool.token_b_liquidity }
+                };
+                quote_liquidity(a).total_cmp(&quote_liquidity(b))
+            })
+    }
+
+    /// Cherche les pools Raydium V4 de la paire via getProgramAccounts (filtre memcmp sur les deux mints,
+    /// dans les deux sens base/quote) et retourne la plus profonde, parsée par le PoolParser
+    async fn lookup_raydium_pool(&self, token: &Pubkey, quote: &Pubkey) -> Result<PoolInfo> {
+        let program_id = Pubkey::from_str(RAYDIUM_V4_PROGRAM)?;
+
+        let mut parser = PoolParser::new(Arc::clone(&self.async_rpc));
+        parser.set_sol_price(self.get_sol_price_cached().await?);
+        parser.batch_account_fetches = self.config.batch_account_fetches;
+        parser.assume_default_decimals = self.config.assume_default_decimals;
+        parser.default_decimals = self.config.default_decimals;
+        parser.decimals_fallback_ttl = Duration::from_secs(self.config.decimals_fallback_ttl_secs);
+
+        // getProgramAccounts est lent sur les gros programmes : borné par le budget d'une analyse
+        let deadline = self.new_deadline();
+        let mut pools = Vec::new();
+        for (base_mint, quote_mint) in [(token, quote), (quote, token)] {
+            let accounts = deadline.run(async {
+                self.async_rpc.get_program_accounts_with_config(
+                    &program_id,
+                    RpcProgramAccountsConfig {
+                        filters: Some(vec![
+                            RpcFilterType::DataSize(RaydiumAmmInfo::LEN as u64),
+                            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(RaydiumAmmInfo::BASE_MINT_OFFSET, base_mint.as_ref())),
+                            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(RaydiumAmmInfo::QUOTE_MINT_OFFSET, quote_mint.as_ref())),
+                        ]),
+                        account_config: RpcAccountInfoConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ).await
+                    .map_err(|e| anyhow!("Erreur RPC getProgramAccounts: {}", e))
+            }).await?;
+
+            for (pool_id, account) in accounts {
+                match parser.parse_pool_account(&account.data, &pool_id, DexType::RaydiumV4, program_id).await {
+                    Ok(pool) => pools.push(pool),
+                    Err(e) => log::debug!("Pool Raydium {} ignorée: {}", pool_id, e),
+                }
+            }
         }
+
+        Self::deepest_pool_for_pair(pools.iter(), token, quote)
+            .cloned()
+            .ok_or_else(|| anyhow!("Aucune pool Raydium V4 trouvée pour {}/{}", token, quote))
     }
 
 
     pub async fn start_sol_price_updater(&self) {
         let sol_price = self.sol_price.clone();
         let rpc = self.async_rpc.clone();
+        let config = Arc::clone(&self.config);
+        let cache_path = self.config.sol_price_cache_path.clone();
         
-        // Premier appel immédiat au lancement
-        match Self::fetch_sol_price_from_pool(&rpc).await {
-            Ok(price) => {
-                let mut price_guard = sol_price.write().await;
-                *price_guard = Some(price);
-            }
-            Err(e) => {
-                log::error!("❌ ERREUR CRITIQUE: Impossible de récupérer le prix SOL depuis CoinGecko: {}", e);
-                // Utiliser un prix SOL fixe par défaut
-                let mut price_guard = sol_price.write().await;
-                *price_guard = Some(221.0); // Prix SOL par défaut
-                log::warn!("⚠️ Utilisation prix SOL par défaut: $221.00");
+        // Démarrer tout de suite avec le dernier prix persisté s'il est assez récent
+        let seeded = match &cache_path {
+            Some(path) => match Self::load_persisted_sol_price(path, self.config.sol_price_cache_max_age_secs).await {
+                Some(persisted) => {
+                    log::info!("💾 Prix SOL restauré depuis {}: ${:.2}", path.display(), persisted.price);
+                    *sol_price.write().await = Some(persisted.price);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        };
+
+        // Sans seed : premier appel immédiat au lancement
+        let mut has_real_price = seeded;
+        if !seeded {
+            match Self::fetch_sol_price(&rpc, &config).await {
+                Ok(price) => {
+                    let mut price_guard = sol_price.write().await;
+                    *price_guard = Some(price);
+                    drop(price_guard);
+                    Self::persist_sol_price(cache_path.as_deref(), price).await;
+                    has_real_price = true;
+                }
+                Err(e) => {
+                    log::error!("❌ ERREUR CRITIQUE: Impossible de récupérer le prix SOL: {}", e);
+                    // Utiliser le prix SOL de repli de la config
+                    let mut price_guard = sol_price.write().await;
+                    *price_guard = Some(config.sol_price_fallback);
+                    log::warn!("⚠️ Utilisation prix SOL par défaut: ${:.2}", config.sol_price_fallback);
+                }
             }
         }
         
-        // Mise à jour périodique toutes les 10 minutes
+        // Mise à jour toutes les 10 minutes ; tant qu'on tourne sur le prix de repli, réessayer
+        // avec un backoff court (5s doublé jusqu'à 60s). Un prix restauré est rafraîchi tout de suite.
+        const REFRESH_EVERY: Duration = Duration::from_secs(600);
+        const RETRY_MIN: Duration = Duration::from_secs(5);
+        const RETRY_MAX: Duration = Duration::from_secs(60);
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(600)); // 10 minutes = 600 secondes
+            let mut retry_delay = RETRY_MIN;
+            let mut delay = match (seeded, has_real_price) {
+                (true, _) => Duration::ZERO,
+                (false, true) => REFRESH_EVERY,
+                (false, false) => RETRY_MIN,
+            };
             
             loop {
-                interval.tick().await;
+                tokio::time::sleep(delay).await;
                 
-                match Self::fetch_sol_price_from_pool(&rpc).await {
+                match Self::fetch_sol_price(&rpc, &config).await {
                     Ok(price) => {
                         let mut price_guard = sol_price.write().await;
                         *price_guard = Some(price);
+                        drop(price_guard);
+                        Self::persist_sol_price(cache_path.as_deref(), price).await;
+                        if !has_real_price {
+                            log::info!("✅ Prix SOL obtenu après repli: ${:.2}", price);
+                        }
+                        has_real_price = true;
+                        delay = REFRESH_EVERY;
                     }
-                    Err(e) => {
-                        log::warn!("⚠️ Échec mise à jour prix SOL depuis CoinGecko: {}", e);
+                    Err(e) if has_real_price => {
+                        log::warn!("⚠️ Échec mise à jour prix SOL: {}", e);
                         // Garder le prix actuel
+                        delay = REFRESH_EVERY;
+                    }
+                    Err(e) => {
+                        retry_delay = (retry_delay * 2).min(RETRY_MAX);
+                        delay = retry_delay;
+                        log::warn!("⚠️ Prix SOL toujours indisponible (nouvel essai dans {:?}): {}", delay, e);
                     }
                 }
             }
         });
     }
 
-    /// Récupère le prix SOL depuis l'API CoinGecko
-    async fn fetch_sol_price_from_pool(_rpc: &AsyncRpcClient) -> Result<f64> {
+    /// Essaie les sources de `sol_price_sources` dans l'ordre jusqu'à obtenir un prix plausible.
+    /// Retourne une erreur (et non un prix par défaut) pour ne jamais persister une valeur inventée.
+    async fn fetch_sol_price(rpc: &Arc<AsyncRpcClient>, config: &BotConfig) -> Result<f64> {
+        let mut errors = Vec::new();
+        for source in &config.sol_price_sources {
+            let result = match source {
+                SolPriceSource::CoinGecko => Self::fetch_sol_price_from_coingecko().await,
+                SolPriceSource::Pyth => Self::fetch_sol_price_from_pyth(rpc, config).await,
+                SolPriceSource::OnChainPool => Self::fetch_sol_price_from_pool(rpc, config).await,
+            };
+            match result {
+                Ok(price) if (config.sol_price_min..=config.sol_price_max).contains(&price) => {
+                    log::info!("💲 Prix SOL: ${:.2} (source: {:?})", price, source);
+                    return Ok(price);
+                }
+                Ok(price) => errors.push(format!(
+                    "{:?}: prix anormal ${:.2} (attendu entre ${:.0}-${:.0})",
+                    source, price, config.sol_price_min, config.sol_price_max
+                )),
+                Err(e) => errors.push(format!("{:?}: {}", source, e)),
+            }
+            log::debug!("Source prix SOL {:?} en échec, source suivante", source);
+        }
+        Err(anyhow!("Aucune source de prix SOL disponible ({})", errors.join(" | ")))
+    }
+
+    /// Prix SOL depuis l'API CoinGecko
+    async fn fetch_sol_price_from_coingecko() -> Result<f64> {
         
         let client = reqwest::Client::new();
         let url = "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
         
-        let response = match client.get(url).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                log::warn!("⚠️ Erreur requête CoinGecko: {}", e);
-                return Ok(221.0);
-            }
-        };
+        let response = client.get(url).send().await
+            .map_err(|e| anyhow!("Erreur requête CoinGecko: {}", e))?;
+        
+        let json: serde_json::Value = response.json().await
+            .map_err(|e| anyhow!("Erreur parsing réponse CoinGecko: {}", e))?;
         
-        let json: serde_json::Value = match response.json().await {
-            Ok(data) => data,
+        json["solana"]["usd"].as_f64()
+            .ok_or_else(|| anyhow!("Prix SOL non trouvé dans la réponse CoinGecko"))
+    }
+
+    /// Prix SOL depuis le compte PriceUpdateV2 SOL/USD de Pyth (rejeté s'il est trop ancien)
+    async fn fetch_sol_price_from_pyth(rpc: &AsyncRpcClient, config: &BotConfig) -> Result<f64> {
+        let account = rpc.get_account(&config.pyth_sol_usd_account).await
+            .map_err(|e| anyhow!("Erreur RPC compte Pyth: {}", e))?;
+        let update = PythPriceUpdate::from_account_data(&account.data)?;
+
+        let age_secs = chrono::Utc::now().timestamp() - update.publish_time;
+        if age_secs > config.pyth_max_staleness_secs as i64 {
+            return Err(anyhow!("Prix Pyth périmé ({}s)", age_secs));
+        }
+        Ok(update.price_f64())
+    }
+
+    /// Prix SOL depuis les réserves de la pool Raydium V4 WSOL/USDC `sol_usdc_pool` (USDC par SOL, unités UI)
+    async fn fetch_sol_price_from_pool(rpc: &Arc<AsyncRpcClient>, config: &BotConfig) -> Result<f64> {
+        let parser = PoolParser::new(Arc::clone(rpc));
+        let pool = parser.parse_pool(&config.sol_usdc_pool, DexType::RaydiumV4, Pubkey::from_str(RAYDIUM_V4_PROGRAM)?).await?;
+
+        let wsol_mint = Pubkey::from_str(WSOL_MINT)?;
+        let usdc_mint = Pubkey::from_str(USDC_MINT)?;
+        let (sol_reserve, usdc_reserve) = if pool.token_a_mint == wsol_mint && pool.token_b_mint == usdc_mint {
+            (pool.token_a_liquidity, pool.token_b_liquidity)
+        } else if pool.token_a_mint == usdc_mint && pool.token_b_mint == wsol_mint {
+            (pool.token_b_liquidity, pool.token_a_liquidity)
+        } else {
+            return Err(anyhow!("La pool {} n'est pas une pool WSOL/USDC", config.sol_usdc_pool));
+        };
+        if sol_reserve <= 0.0 {
+            return Err(anyhow!("Réserve WSOL nulle dans la pool {}", config.sol_usdc_pool));
+        }
+        Ok(usdc_reserve / sol_reserve)
+    }
+
+    /// Charge le dernier prix SOL persisté, s'il existe et n'est pas plus vieux que `max_age_secs`
+    async fn load_persisted_sol_price(path: &Path, max_age_secs: u64) -> Option<PersistedSolPrice> {
+        let content = tokio::fs::read(path).await.ok()?;
+        let persisted: PersistedSolPrice = match serde_json::from_slice(&content) {
+            Ok(persisted) => persisted,
             Err(e) => {
-                log::warn!("⚠️ Erreur parsing réponse CoinGecko: {}", e);
-                return Ok(221.0);
+                log::warn!("⚠️ Cache prix SOL illisible ({}): {}", path.display(), e);
+                return None;
             }
         };
-        
-        let sol_price = match json["solana"]["usd"].as_f64() {
-            Some(price) => price,
-            None => {
-                log::warn!("⚠️ Prix SOL non trouvé dans la réponse CoinGecko");
-                return Ok(221.0);
-            }
+
+        let age_secs = chrono::Utc::now().timestamp() - persisted.updated_at;
+        if age_secs < 0 || age_secs as u64 > max_age_secs {
+            log::warn!("⚠️ Cache prix SOL trop ancien ({}s), ignoré", age_secs);
+            return None;
+        }
+        Some(persisted)
+    }
+
+    /// Persiste un prix SOL valide (erreurs d'écriture simplement loggées)
+    async fn persist_sol_price(path: Option<&Path>, price: f64) {
+        let Some(path) = path else {
+            return;
         };
-        
-        // Validation du prix
-        if sol_price < 50.0 || sol_price > 500.0 {
-            log::warn!("⚠️ Prix SOL anormal: ${:.2} (attendu entre $50-$500)", sol_price);
-            return Ok(221.0);
+        let persisted = PersistedSolPrice {
+            price,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        let result = match serde_json::to_vec(&persisted) {
+            Ok(json) => tokio::fs::write(path, json).await.map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("⚠️ Impossible de persister le prix SOL dans {}: {}", path.display(), e);
         }
-        
-        Ok(sol_price)
     }
 
     /// Récupère le prix SOL depuis le cache
@@ -402,7 +691,7 @@ impl MonitoringEngine {
         let price_guard = self.sol_price.read().await;
         match *price_guard {
             Some(price) => Ok(price),
-            None => Err(anyhow!("Prix SOL non disponible - attente du cache")),
+            None => Err(AnalysisError::SolPriceUnavailable.into()),
         }
     }
 
@@ -418,43 +707,70 @@ impl MonitoringEngine {
         signature: &str,
         _invested_usd: f64,
     ) -> Result<(f64, f64, f64)> {
-        let start_time = Instant::now();
+        let swap = self
+            .calculate_tokens_received_and_mcap_impact_within(signature, _invested_usd, &self.new_deadline())
+            .await?;
+        Ok((swap.tokens_received, swap.mcap_before, swap.mcap_impact_pct))
+    }
 
-let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transaction_with_config(
-    &signature.parse()?,
-    RpcTransactionConfig {
-        encoding: Some(UiTransactionEncoding::JsonParsed),
-        commitment: Some(CommitmentConfig::confirmed()),
-        max_supported_transaction_version: Some(0),
-    },
-)).await {
-    Ok(Ok(res)) => res,
-    Ok(Err(e)) => return Err(anyhow!("Erreur RPC: {}", e)),
-    Err(_) => return Err(anyhow!("⏰ Timeout RPC lors de la récupération de la transaction")),
-};
+    async fn calculate_tokens_received_and_mcap_impact_within(
+        &self,
+        signature: &str,
+        _invested_usd: f64,
+        deadline: &Deadline,
+    ) -> Result<VictimSwapAnalysis> {
+        let tx_result = self.fetch_parsed_transaction(signature, deadline).await?;
         
         let meta = tx_result.transaction.meta.as_ref()
             .ok_or_else(|| anyhow!("Pas de métadonnées dans la transaction"))?;
         
-        // Extraire l'owner utilisateur
-        let user_owner = self.extract_user_owner_from_transaction(&tx_result)?;
-        
-        // Calculer la vraie valeur d'investissement
-        let real_invested_usd = self.get_investment_value_fast(signature).await?;
+        // Extraire le swapper (distinct du fee payer si la transaction est relayée)
+        let parties = self.extract_transaction_parties(&tx_result)?;
+        let user_owner = parties.swapper.clone();
+
+        // Ne jamais analyser nos propres trades comme ceux d'une cible
+        if self.config.skip_own_transactions && user_owner == self.config.keypair.pubkey().to_string() {
+            return Err(AnalysisError::OwnTransaction.into());
+        }
         
         // Analyser les tokens reçus
-        let (token_mint, tokens_received) = match (&meta.pre_token_balances, &meta.post_token_balances) {
+        let (token_mint, tokens_received, tokens_received_estimated) = match (&meta.pre_token_balances, &meta.post_token_balances) {
             (
                 solana_transaction_status::option_serializer::OptionSerializer::Some(pre),
                 solana_transaction_status::option_serializer::OptionSerializer::Some(post)
             ) => {
-                self.analyze_tokens_from_pre_post_balances(pre, post, &user_owner).await?
+                // Un aller-retour ne doit pas être pris pour un achat (jambe choisie arbitrairement)
+                if self.config.detect_round_trips {
+                    if let Some((mint, gross_flow)) =
+                        Self::detect_round_trip(pre, post, &user_owner, self.config.round_trip_max_net_ratio)
+                    {
+                        return Err(AnalysisError::RoundTrip { mint, gross_flow }.into());
+                    }
+                }
+                match self.analyze_tokens_from_pre_post_balances(pre, post, &user_owner).await {
+                    Ok((mint, amount, SwapDirection::Buy)) => (mint, amount, false),
+                    // Seuls les achats déplacent la mcap à la hausse
+                    Ok((mint, _, SwapDirection::Sell)) => {
+                        return Err(AnalysisError::SellSwap { mint: mint.to_string() }.into());
+                    }
+                    Err(e) if self.config.tokens_received_fallback => {
+                        let account_keys = Self::all_account_keys(&tx_result);
+                        match Self::tokens_received_from_signer_atas(pre, post, &user_owner, &account_keys) {
+                            Some((mint, amount)) => {
+                                log::info!("🩹 TX: {} | tokens reçus estimés via les ATAs du signataire ({:.2} de {})", signature, amount, mint);
+                                (mint, amount, true)
+                            }
+                            None => return Err(e),
+                        }
+                    }
+                    Err(e) => return Err(e),
+                }
             }
             _ => return Err(anyhow!("Aucun token balance fourni")),
         };
 
         // Récupérer la supply du token
-        let circulating_supply = self.get_circulating_supply(&token_mint).await?;
+        let circulating_supply = self.get_circulating_supply(&token_mint, deadline).await?;
         
         // Calculer l'impact MCap via les pools de la transaction
         let pre_balances = match &meta.pre_token_balances {
@@ -466,143 +782,374 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             _ => &[],
         };
 
-        let (mcap_before, mcap_after, mcap_impact_pct) = match self.calculate_mcap_impact_from_transaction_pools(
+        // Mint réellement dépensé par la cible : la pool retenue doit être cotée dans ce mint
+        let victim_quote_mint = if self.config.reject_off_route_quote_pools {
+            let sol_price = self.get_sol_price_cached().await?;
+            Self::victim_input_quote_mint(&tx_result, &parties, sol_price)
+        } else {
+            None
+        };
+
+        let impact = match self.calculate_mcap_impact_from_transaction_pools(
             pre_balances,
             post_balances,
             &token_mint,
-            _invested_usd,
             tokens_received,
             circulating_supply,
+            victim_quote_mint.as_ref(),
+            deadline,
         ).await {
             Ok(result) => {
                 result
             }
-            Err(e) => {
-                return Err(anyhow!("Aucune pool DEX détectée dans la transaction - Transaction non analysable"));
-            }
+            // Budget épuisé et pool mal parsée restent distincts ; le reste signifie qu'aucune pool n'est exploitable
+            Err(e) => match e.downcast_ref::<AnalysisError>() {
+                Some(AnalysisError::RpcTimeout { .. } | AnalysisError::PoolParseFailed { .. }) => return Err(e),
+                _ => return Err(AnalysisError::NoDexPool.into()),
+            },
         };
         
-        let total_time = start_time.elapsed();
-        
-        Ok((tokens_received, mcap_before, mcap_impact_pct))
+        Ok(VictimSwapAnalysis {
+            tokens_received,
+            mcap_before: impact.mcap_before,
+            mcap_impact_pct: impact.mcap_impact_pct,
+            tokens_received_estimated,
+            quote_mint: impact.quote_mint,
+            reserve_share: impact.reserve_share,
+            parties,
+            impact_debug: impact.debug,
+        })
+    }
+
+    /// Toutes les clés de comptes dans l'ordre des `account_index` (statiques puis adresses chargées via ALT).
+    /// Si les lookup tables d'une transaction v0 ne sont pas résolues, seules les clés statiques sont
+    /// retournées : les signataires y sont toujours, seuls les index des comptes chargés restent inconnus.
+    fn all_account_keys(tx_result: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<String> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        let message = match &tx_result.transaction.transaction {
+            solana_transaction_status::EncodedTransaction::Json(ui_tx) => &ui_tx.message,
+            _ => return Vec::new(),
+        };
+        match message {
+            // jsonParsed : les adresses chargées sont déjà dans account_keys (source "lookupTable")
+            solana_transaction_status::UiMessage::Parsed(parsed) => {
+                parsed.account_keys.iter().map(|key| key.pubkey.clone()).collect()
+            }
+            // json brut : clés statiques uniquement, compléter avec meta.loaded_addresses (writable puis readonly)
+            solana_transaction_status::UiMessage::Raw(raw) => {
+                let mut keys = raw.account_keys.clone();
+                let loaded = tx_result.transaction.meta.as_ref().map(|meta| &meta.loaded_addresses);
+                match loaded {
+                    Some(OptionSerializer::Some(loaded)) => {
+                        keys.extend(loaded.writable.iter().cloned());
+                        keys.extend(loaded.readonly.iter().cloned());
+                    }
+                    _ => {
+                        let lookups = raw.address_table_lookups.as_ref().map_or(0, |lookups| lookups.len());
+                        if lookups > 0 {
+                            log::debug!("🗂️ {} lookup table(s) non résolue(s) : comptes chargés ignorés", lookups);
+                        }
+                    }
+                }
+                keys
+            }
+        }
+    }
+
+    /// Fallback quand aucune balance n'est attribuée au signataire par `owner` :
+    /// plus grand delta positif d'un token non-système sur un ATA du signataire (adresse dérivée)
+    fn tokens_received_from_signer_atas(
+        pre_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        post_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        user_owner: &str,
+        account_keys: &[String],
+    ) -> Option<(Pubkey, f64)> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        let wallet = Pubkey::from_str(user_owner).ok()?;
+        let mut best: Option<(Pubkey, f64)> = None;
+        for post_balance in post_balances {
+            if SYSTEM_TOKENS.contains(post_balance.mint.as_str()) {
+                continue;
+            }
+            let Ok(mint) = Pubkey::from_str(&post_balance.mint) else {
+                continue;
+            };
+            let token_program = match &post_balance.program_id {
+                OptionSerializer::Some(program) => Pubkey::from_str(program).unwrap_or(spl_token::id()),
+                _ => spl_token::id(),
+            };
+            let ata = spl_associated_token_account::get_associated_token_address_with_program_id(&wallet, &mint, &token_program);
+            if account_keys.get(post_balance.account_index as usize) != Some(&ata.to_string()) {
+                continue;
+            }
+
+            let pre_amount = pre_balances.iter()
+                .find(|p| p.account_index == post_balance.account_index)
+                .and_then(|p| p.ui_token_amount.ui_amount)
+                .unwrap_or(0.0);
+            let diff = post_balance.ui_token_amount.ui_amount.unwrap_or(0.0) - pre_amount;
+            if diff > 0.0 && best.as_ref().is_none_or(|(_, d)| diff > *d) {
+                best = Some((mint, diff));
+            }
+        }
+        best
     }
 
     /// Extrait l'owner utilisateur de la transaction
+    #[tracing::instrument(level = "debug", name = "extract_user", skip_all)]
     fn extract_user_owner_from_transaction(
         &self, 
         tx_result: &EncodedConfirmedTransactionWithStatusMeta,
     ) -> Result<String> {
-        let tx_meta = &tx_result.transaction;
-        let message = match &tx_meta.transaction {
+        Ok(self.extract_transaction_parties(tx_result)?.swapper)
+    }
+
+    /// Fee payer (premier signataire) et swapper. Dans une transaction relayée/sponsorisée, le swapper
+    /// est le signataire dont les balances de tokens non-système bougent le plus, pas celui qui paie les frais.
+    fn extract_transaction_parties(
+        &self,
+        tx_result: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<TransactionParties> {
+        let signers = Self::transaction_signers(tx_result)?;
+        let fee_payer = signers.first().cloned()
+            .ok_or_else(|| anyhow!("Aucun signataire trouvé dans la transaction"))?;
+
+        let swapper = if self.config.resolve_swapper_from_balances && signers.len() > 1 {
+            Self::signer_with_largest_token_change(tx_result, &signers).unwrap_or_else(|| fee_payer.clone())
+        } else {
+            fee_payer.clone()
+        };
+        if swapper != fee_payer {
+            log::debug!("🧾 Transaction relayée: fee payer {} ≠ swapper {}", fee_payer, swapper);
+        }
+
+        Ok(TransactionParties { fee_payer, swapper })
+    }
+
+    /// Signataires de la transaction, dans l'ordre du message
+    fn transaction_signers(tx_result: &EncodedConfirmedTransactionWithStatusMeta) -> Result<Vec<String>> {
+        let message = match &tx_result.transaction.transaction {
             solana_transaction_status::EncodedTransaction::Json(ui_tx) => &ui_tx.message,
             _ => return Err(anyhow!("Transaction non parsable")),
         };
 
-        let account_keys = match message {
-            solana_transaction_status::UiMessage::Parsed(parsed) => &parsed.account_keys,
-            solana_transaction_status::UiMessage::Raw(raw) => {
-                if let Some(first_key) = raw.account_keys.first() {
-                    return Ok(first_key.to_string());
+        Ok(match message {
+            // Le format parsé porte les flags signer, sans supposer qu'ils sont en tête
+            solana_transaction_status::UiMessage::Parsed(parsed) => parsed.account_keys.iter()
+                .filter(|key| key.signer)
+                .map(|key| key.pubkey.clone())
+                .collect(),
+            // Format brut : les signataires sont par construction les premiers comptes
+            solana_transaction_status::UiMessage::Raw(raw) => raw.account_keys.iter()
+                .take(raw.header.num_required_signatures as usize)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Signataire dont la variation absolue de balances de tokens non-système (unités UI) est la plus grande
+    fn signer_with_largest_token_change(
+        tx_result: &EncodedConfirmedTransactionWithStatusMeta,
+        signers: &[String],
+    ) -> Option<String> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        let meta = tx_result.transaction.meta.as_ref()?;
+        let (OptionSerializer::Some(pre), OptionSerializer::Some(post)) = (&meta.pre_token_balances, &meta.post_token_balances) else {
+            return None;
+        };
+
+        // (owner, account_index) -> (pre, post)
+        let mut accounts: AHashMap<(&str, u8), (f64, f64)> = AHashMap::new();
+        for (balances, is_post) in [(pre, false), (post, true)] {
+            for balance in balances {
+                let OptionSerializer::Some(owner) = &balance.owner else { continue };
+                if SYSTEM_TOKENS.contains(balance.mint.as_str()) || !signers.contains(owner) {
+                    continue;
                 }
-                return Err(anyhow!("Aucun compte trouvé dans la transaction"));
+                let amount = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+                let entry = accounts.entry((owner.as_str(), balance.account_index)).or_insert((0.0, 0.0));
+                if is_post { entry.1 = amount } else { entry.0 = amount }
             }
-        };
+        }
 
-        // Chercher le signer principal
-        if let Some(first_signer) = account_keys.first() {
-            Ok(first_signer.pubkey.clone())
-        } else {
-            Err(anyhow!("Aucun signataire trouvé dans la transaction"))
+        let mut per_owner: AHashMap<&str, f64> = AHashMap::new();
+        for ((owner, _), (pre, post)) in accounts {
+            *per_owner.entry(owner).or_insert(0.0) += (post - pre).abs();
         }
+        per_owner.into_iter()
+            .filter(|(_, change)| *change > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(owner, _)| owner.to_string())
     }
 
-    /// Analyse les tokens depuis les balances pre/post
-    async fn analyze_tokens_from_pre_post_balances(
-        &self, 
+    /// Mint de quote d'une pool : le côté qui n'est pas le token analysé
+    fn pool_quote_mint(pool: &PoolInfo, token_mint: &Pubkey) -> Pubkey {
+        if pool.token_a_mint == *token_mint { pool.token_b_mint } else { pool.token_a_mint }
+    }
+
+    /// Mint de quote (WSOL ou USDC) dont la cible a le plus dépensé en USD, avec les mêmes balances que
+    /// `get_investment_value_within` : SOL natif (hors frais s'il les paie) assimilé au WSOL.
+    fn victim_input_quote_mint(
+        tx_result: &EncodedConfirmedTransactionWithStatusMeta,
+        parties: &TransactionParties,
+        sol_price: f64,
+    ) -> Option<Pubkey> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        let meta = tx_result.transaction.meta.as_ref()?;
+        let swapper = parties.swapper.as_str();
+
+        // Sorties SOL natives (lamports → SOL), frais de transaction exclus
+        let mut sol_out = Self::account_index_in_message(tx_result, swapper).ok()
+            .and_then(|index| Some((*meta.pre_balances.get(index)?, *meta.post_balances.get(index)?)))
+            .map(|(pre, post)| {
+                let fee = if parties.fee_payer == parties.swapper { meta.fee } else { 0 };
+                pre.saturating_sub(post).saturating_sub(fee) as f64 / 1e9
+            })
+            .unwrap_or(0.0);
+        let mut usdc_out = 0.0;
+
+        if let (OptionSerializer::Some(pre), OptionSerializer::Some(post)) = (&meta.pre_token_balances, &meta.post_token_balances) {
+            for pre_balance in pre {
+                if !matches!(&pre_balance.owner, OptionSerializer::Some(owner) if owner == swapper) {
+                    continue;
+                }
+                let post_amount = post.iter()
+                    .find(|p| p.account_index == pre_balance.account_index)
+                    .and_then(|p| p.ui_token_amount.ui_amount)
+                    .unwrap_or(0.0);
+                let spent = pre_balance.ui_token_amount.ui_amount.unwrap_or(0.0) - post_amount;
+                if spent <= 0.0 {
+                    continue;
+                }
+                match pre_balance.mint.as_str() {
+                    WSOL_MINT => sol_out += spent,
+                    USDC_MINT => usdc_out += spent,
+                    _ => {}
+                }
+            }
+        }
+
+        let sol_out_usd = sol_out * sol_price;
+        if sol_out_usd <= 0.0 && usdc_out <= 0.0 {
+            return None;
+        }
+        let mint = if usdc_out > sol_out_usd { USDC_MINT } else { WSOL_MINT };
+        Pubkey::from_str(mint).ok()
+    }
+
+    /// Détecte un aller-retour : pour chaque token non-système touché par l'utilisateur,
+    /// la variation nette reste négligeable devant le flux brut qui a traversé les autres comptes.
+    /// Retourne le mint avec le plus gros flux brut.
+    fn detect_round_trip(
         pre_balances: &[solana_transaction_status::UiTransactionTokenBalance],
         post_balances: &[solana_transaction_status::UiTransactionTokenBalance],
         user_owner: &str,
-    ) -> Result<(Pubkey, f64)> {
-        
-        let mut balance_changes: AHashMap<String, (f64, f64, f64)> = AHashMap::new();
-        
-        // Analyser les changements de balances pour l'utilisateur
-        // 1. D'abord, analyser les balances pre existantes
-        for pre_balance in pre_balances {
-            let mint = &pre_balance.mint;
-            
-            // Filtrer par owner utilisateur
-            let is_user_balance = match &pre_balance.owner {
-                solana_transaction_status::option_serializer::OptionSerializer::Some(owner) => {
-                    owner == user_owner
-                },
-                _ => false,
-            };
-            
-            if is_user_balance {
-                let pre_amount = pre_balance.ui_token_amount.ui_amount.unwrap_or(0.0);
-                
-                // Chercher le post_balance correspondant
-                if let Some(post_balance) = post_balances.iter().find(|p| 
-                    p.mint == *mint && 
-                    match &p.owner {
-                        solana_transaction_status::option_serializer::OptionSerializer::Some(o) => o == user_owner,
-                        _ => false,
-                    }
-                ) {
-                    let post_amount = post_balance.ui_token_amount.ui_amount.unwrap_or(0.0);
-                    let diff = post_amount - pre_amount;
-                    
-                    balance_changes.insert(mint.clone(), (pre_amount, post_amount, diff));
-                }
+        max_net_ratio: f64,
+    ) -> Option<(String, f64)> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        // (mint, account_index) -> (pre, post, appartient à l'utilisateur)
+        let mut accounts: AHashMap<(&str, u8), (f64, f64, bool)> = AHashMap::new();
+        for balance in pre_balances {
+            let is_user = matches!(&balance.owner, OptionSerializer::Some(o) if o == user_owner);
+            let entry = accounts.entry((balance.mint.as_str(), balance.account_index)).or_insert((0.0, 0.0, is_user));
+            entry.0 = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+        }
+        for balance in post_balances {
+            let is_user = matches!(&balance.owner, OptionSerializer::Some(o) if o == user_owner);
+            let entry = accounts.entry((balance.mint.as_str(), balance.account_index)).or_insert((0.0, 0.0, is_user));
+            entry.1 = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+            entry.2 |= is_user;
+        }
+
+        // mint -> (variation nette utilisateur, somme des |variations| des autres comptes, touché par l'utilisateur)
+        let mut per_mint: AHashMap<&str, (f64, f64, bool)> = AHashMap::new();
+        for ((mint, _), (pre, post, is_user)) in &accounts {
+            if SYSTEM_TOKENS.contains(*mint) {
+                continue;
+            }
+            let entry = per_mint.entry(mint).or_insert((0.0, 0.0, false));
+            if *is_user {
+                entry.0 += post - pre;
+                entry.2 = true;
+            } else {
+                entry.1 += (post - pre).abs();
             }
         }
-        
-        // 2. Ensuite, analyser les nouvelles balances post (tokens reçus sans balance pre)
-                for post_balance in post_balances {
-                    let mint = &post_balance.mint;
-                    
-            // Filtrer par owner utilisateur
-            let is_user_balance = match &post_balance.owner {
-                solana_transaction_status::option_serializer::OptionSerializer::Some(owner) => {
-                    owner == user_owner
-                },
-                _ => false,
-            };
-            
-            if is_user_balance {
-                // Si ce token n'est pas déjà dans balance_changes, c'est un nouveau token reçu
-                if !balance_changes.contains_key(mint) {
-                    let post_amount = post_balance.ui_token_amount.ui_amount.unwrap_or(0.0);
-                    let diff = post_amount; // Nouveau token = diff = post_amount
-                    
-                    balance_changes.insert(mint.clone(), (0.0, post_amount, diff));
-                }
+
+        let mut best: Option<(String, f64)> = None;
+        for (mint, (user_net, others_abs, touched)) in per_mint {
+            if !touched {
+                continue;
+            }
+            // Chaque token entre puis sort des pools : le flux brut est la moitié des mouvements
+            let gross_flow = others_abs / 2.0;
+            if gross_flow <= 1.0 || user_net.abs() > max_net_ratio * gross_flow {
+                // Un vrai achat/vente sur ce mint : ce n'est pas un aller-retour
+                return None;
+            }
+            if best.as_ref().is_none_or(|(_, g)| gross_flow > *g) {
+                best = Some((mint.to_string(), gross_flow));
             }
         }
-        
-        // Filtrer les tokens non-système avec des changements positifs raisonnables
-        let mut candidates = Vec::new();
-        for (mint, (_pre, _post, diff)) in &balance_changes {
-            if !SYSTEM_TOKENS.contains(mint.as_str()) && *diff > 1.0 && *diff < 1000000000.0 {
-                candidates.push((mint.clone(), *diff));
+        best
+    }
+
+    /// Token non-système échangé par l'utilisateur et sens du swap. Les variations sont sommées par mint sur
+    /// tous ses comptes ; achat : plus forte hausse dans [min_tokens_received, max_tokens_received],
+    /// sinon vente : plus forte baisse. Égalités départagées par l'adresse du mint (résultat déterministe).
+    #[tracing::instrument(level = "debug", name = "analyze_tokens", skip_all)]
+    async fn analyze_tokens_from_pre_post_balances(
+        &self, 
+        pre_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        post_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        user_owner: &str,
+    ) -> Result<(Pubkey, f64, SwapDirection)> {
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        // (mint, account_index) -> (pre, post) pour les comptes de l'utilisateur
+        let mut accounts: AHashMap<(&str, u8), (f64, f64)> = AHashMap::new();
+        for (balances, is_post) in [(pre_balances, false), (post_balances, true)] {
+            for balance in balances {
+                let OptionSerializer::Some(owner) = &balance.owner else { continue };
+                if owner != user_owner || SYSTEM_TOKENS.contains(balance.mint.as_str()) {
+                    continue;
+                }
+                let amount = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+                let entry = accounts.entry((balance.mint.as_str(), balance.account_index)).or_insert((0.0, 0.0));
+                if is_post { entry.1 = amount } else { entry.0 = amount }
             }
         }
-        
-        if candidates.is_empty() {
-            return Err(anyhow!("Aucun token non-système reçu détecté"));
+
+        let mut changes: AHashMap<&str, f64> = AHashMap::new();
+        for ((mint, _), (pre, post)) in accounts {
+            *changes.entry(mint).or_insert(0.0) += post - pre;
         }
-        
-        // Prendre le premier candidat (le plus petit changement positif)
-        let (final_mint, tokens_received) = &candidates[0];
-        let token_mint = Pubkey::from_str(final_mint)?;
-        
-        
-        Ok((token_mint, *tokens_received))
+        // Plus grande variation d'abord, puis ordre des mints
+        let mut changes: Vec<(&str, f64)> = changes.into_iter().collect();
+        changes.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()).then_with(|| a.0.cmp(b.0)));
+
+        let bought = changes.iter().find(|(_, diff)| {
+            *diff > self.config.min_tokens_received && *diff < self.config.max_tokens_received
+        });
+        let (mint, amount, direction) = match bought {
+            Some((mint, diff)) => (*mint, *diff, SwapDirection::Buy),
+            None => match changes.iter().find(|(_, diff)| -*diff > self.config.min_tokens_received) {
+                Some((mint, diff)) => (*mint, -*diff, SwapDirection::Sell),
+                None => return Err(AnalysisError::NoNonSystemToken.into()),
+            },
+        };
+
+        Ok((Pubkey::from_str(mint)?, amount, direction))
     }
 
-    /// Récupère la supply circulante d'un token
-    async fn get_circulating_supply(&self, token_mint: &Pubkey) -> Result<f64> {
+    /// Récupère la supply circulante d'un token, en unités UI (décimales du mint appliquées)
+    #[tracing::instrument(level = "debug", name = "get_supply", skip_all, fields(mint = %token_mint))]
+    async fn get_circulating_supply(&self, token_mint: &Pubkey, deadline: &Deadline) -> Result<f64> {
         // Vérifier le cache d'abord
         {
         let cache = self.supply_cache.read().await;
@@ -612,10 +1159,21 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             }
         }
         }
+
+        if self.offline_transactions.is_some() {
+            return Err(anyhow!("Supply de {} inconnue hors-ligne (à renseigner dans offline_supplies)", token_mint));
+        }
         
         // Récupérer la supply depuis la blockchain
-        let mint_info = self.async_rpc.get_token_supply(token_mint).await?;
-        let total_supply = mint_info.ui_amount.unwrap_or(0.0);
+        let mint_info = deadline.run(async {
+            self.async_rpc.get_token_supply(token_mint).await
+                .map_err(|e| anyhow!("Erreur RPC: {}", e))
+        }).await?;
+        // Toujours en unités UI : montant brut ramené avec les décimales du mint
+        // (ui_amount peut être absent, et ne doit jamais être redivisé par l'appelant)
+        let raw_supply: f64 = mint_info.amount.parse()
+            .map_err(|e| anyhow!("Supply invalide pour {}: {}", token_mint, e))?;
+        let total_supply = raw_supply / 10f64.powi(mint_info.decimals as i32);
                 
                 // Mettre en cache
         {
@@ -627,31 +1185,106 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
     }
 
     /// Calcule l'impact MCap avec les pools extraites de la transaction
+    #[allow(clippy::too_many_arguments)]
     async fn calculate_mcap_impact_from_transaction_pools(
         &self, 
         pre_balances: &[solana_transaction_status::UiTransactionTokenBalance],
         post_balances: &[solana_transaction_status::UiTransactionTokenBalance],
         token_mint: &Pubkey,
-        _invested_usd: f64,
         tokens_received: f64,
         circulating_supply: f64,
-    ) -> Result<(f64, f64, f64)> {
-        // 1. Identifier les owners de pools (Vault Authority, Market, etc.)
-        let pool_owners = self.identify_pool_owners(pre_balances, post_balances)?;
-        
-        if pool_owners.is_empty() {
-            return Err(anyhow!("Aucun owner de pool identifié dans la transaction"));
+        victim_quote_mint: Option<&Pubkey>,
+        deadline: &Deadline,
+    ) -> Result<McapImpact> {
+        // 0. Token encore sur sa bonding curve Pump.fun : modèle dédié (nécessite le compte de la curve, donc le RPC)
+        if self.config.analyze_pumpfun && self.offline_transactions.is_none() {
+            if let Some(pool) = self.pumpfun_pool_from_balances(pre_balances, post_balances, token_mint, deadline).await? {
+                let sol_price = self.get_sol_price_cached().await?;
+                return self.calculate_mcap_impact_single_pool(&pool, token_mint, tokens_received, circulating_supply, sol_price).await;
+            }
         }
-        
-        // 2. Extraire les pools utilisées
-        let pools = self.extract_pools_from_balances(pre_balances, post_balances, &pool_owners, token_mint)?;
+
+        let pools = {
+            let _span = tracing::debug_span!("extract_pools").entered();
+
+            // 1. Identifier les owners de pools (Vault Authority, Market, etc.)
+            let pool_owners = self.identify_pool_owners(pre_balances, post_balances)?;
+            
+            if pool_owners.is_empty() {
+                return Err(anyhow!("Aucun owner de pool identifié dans la transaction"));
+            }
+            
+            // 2. Extraire les pools utilisées
+            let pools = self.extract_pools_from_balances(pre_balances, post_balances, &pool_owners, token_mint)?;
+            if self.config.dedup_extracted_pools {
+                Self::dedup_pools(pools)
+            } else {
+                pools
+            }
+        };
         
         if pools.is_empty() {
             return Err(anyhow!("Aucune pool extraite de la transaction"));
         }
         
         // 3. Calculer l'impact MCap avec ces pools
-        self.calculate_mcap_impact_with_extracted_pools(pools, token_mint, tokens_received, circulating_supply).await
+        self.calculate_mcap_impact_with_extracted_pools(pools, token_mint, tokens_received, circulating_supply, victim_quote_mint).await
+    }
+
+    /// Construit le pool Pump.fun si la bonding curve du token apparaît dans les balances de la transaction
+    async fn pumpfun_pool_from_balances(
+        &self,
+        pre_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        post_balances: &[solana_transaction_status::UiTransactionTokenBalance],
+        token_mint: &Pubkey,
+        deadline: &Deadline,
+    ) -> Result<Option<PoolInfo>> {
+        let bonding_curve = PumpFunBondingCurve::address_for_mint(token_mint);
+        let bonding_curve_str = bonding_curve.to_string();
+
+        let curve_in_tx = pre_balances.iter().chain(post_balances.iter()).any(|balance| {
+            matches!(&balance.owner, solana_transaction_status::option_serializer::OptionSerializer::Some(owner) if *owner == bonding_curve_str)
+        });
+        if !curve_in_tx {
+            return Ok(None);
+        }
+
+        let account = deadline.run(async {
+            self.async_rpc.get_account(&bonding_curve).await
+                .map_err(|e| anyhow!("Erreur RPC: {}", e))
+        }).await?;
+        let curve = PumpFunBondingCurve::from_account_data(&account.data)?;
+        if curve.complete {
+            // Token migré : la liquidité est sur un AMM classique
+            return Ok(None);
+        }
+
+        Ok(Some(PoolInfo {
+            dex_type: DexType::PumpFun,
+            program_id: Pubkey::from_str(PUMPFUN_PROGRAM)?,
+            pool_id: bonding_curve,
+            token_a_mint: *token_mint,
+            token_b_mint: Pubkey::from_str(WSOL_MINT)?,
+            token_a_vault: Pubkey::default(),
+            token_b_vault: bonding_curve,
+            reserve_a: curve.virtual_token_reserves,
+            reserve_b: curve.virtual_sol_reserves,
+            fee_bps: PUMPFUN_FEE_BPS,
+            tick_spacing: None,
+            tick_current: None,
+            bin_step: None,
+            liquidity_usd: 0.0,
+            token_a_liquidity: curve.virtual_token_reserves as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS as i32),
+            token_b_liquidity: curve.virtual_sol_reserves as f64 / 1e9,
+            market_cap_usd: None,
+            token_price_usd: None,
+            total_supply: Some(curve.token_total_supply),
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
+        }))
     }
 
     /// Identifie les owners de pools dans la transaction en utilisant les adresses DEX connues
@@ -673,6 +1306,15 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         
         // Vérifier chaque owner contre les adresses DEX connues
         for owner in all_owners {
+            // Faux positifs connus (market makers, hot wallets CEX...) exclus par l'opérateur
+            if !self.config.pool_owner_denylist.is_empty()
+                && Pubkey::from_str(&owner).is_ok_and(|key| self.config.pool_owner_denylist.contains(&key))
+            {
+                let hits = self.denylist_suppressions.fetch_add(1, Ordering::Relaxed) + 1;
+                log::debug!("🚫 Owner {} ignoré (denylist) | {} exclusions", owner, hits);
+                continue;
+            }
+
             // Vérifier si c'est un programme DEX connu
             if let Some(_dex_name) = is_known_dex_program(&owner) {
                 pool_owners.push(owner);
@@ -772,7 +1414,8 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
                             if mint == &token_mint.to_string() {
                                 pool_token_balance = Some((pre_amount, post_amount, change));
                             } else if mint == &wsol_mint.to_string() || mint == &usdc_mint.to_string() {
-                                pool_quote_balance = Some((pre_amount, post_amount, change));
+                                let quote_mint = if mint == &wsol_mint.to_string() { wsol_mint } else { usdc_mint };
+                                pool_quote_balance = Some((quote_mint, pre_amount, post_amount, change));
                             }
                         }
                     }
@@ -780,7 +1423,7 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             }
             
             // Si on a trouvé les deux balances, créer la pool
-            if let (Some((token_pre, _token_post, _token_change)), Some((quote_pre, _quote_post, _quote_change))) = 
+            if let (Some((token_pre, _token_post, _token_change)), Some((quote_mint, quote_pre, _quote_post, quote_change))) = 
                 (pool_token_balance, pool_quote_balance) {
                 
                 // Déterminer le type de DEX basé sur l'owner
@@ -791,7 +1434,7 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
                     program_id: Pubkey::default(),
                     pool_id: Pubkey::default(),
                     token_a_mint: *token_mint,
-                    token_b_mint: if quote_pre > 0.0 { wsol_mint } else { usdc_mint },
+                    token_b_mint: quote_mint,
                     token_a_vault: Pubkey::default(),
                     token_b_vault: Pubkey::default(),
                     reserve_a: token_pre as u64,
@@ -807,6 +1450,11 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
                     market_cap_usd: None,
                     token_price_usd: None,
                     total_supply: None,
+                    dlmm_bins: None,
+                    dlmm_fee: None,
+                    decimals_assumed: false,
+                    order_book: None,
+                    observed_quote_in: (quote_change > 0.0).then_some(quote_change),
                 };
                 
                 pools.push(pool_info);
@@ -816,6 +1464,29 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         Ok(pools)
     }
 
+    /// Supprime les pools vues plusieurs fois (même pool via plusieurs owners : vault authority + market...).
+    /// Clé (dex, pool_id) quand l'id est connu, sinon (dex, mints, réserves) : deux entrées aux
+    /// réserves identiques sur la même paire sont la même pool, comptée une seule fois.
+    fn dedup_pools(pools: Vec<PoolInfo>) -> Vec<PoolInfo> {
+        let mut seen = HashSet::new();
+        let before = pools.len();
+        let deduped: Vec<PoolInfo> = pools
+            .into_iter()
+            .filter(|pool| {
+                let key = if pool.pool_id != Pubkey::default() {
+                    (pool.dex_type.clone(), pool.pool_id, Pubkey::default(), 0, 0)
+                } else {
+                    (pool.dex_type.clone(), pool.token_a_mint, pool.token_b_mint, pool.reserve_a, pool.reserve_b)
+                };
+                seen.insert(key)
+            })
+            .collect();
+        if deduped.len() < before {
+            log::debug!("🧹 {} pool(s) en double ignorée(s)", before - deduped.len());
+        }
+        deduped
+    }
+
     /// Calcule l'impact MCap avec les pools extraites de la transaction
     async fn calculate_mcap_impact_with_extracted_pools(
         &self,
@@ -823,11 +1494,52 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         token_mint: &Pubkey,
         tokens_received: f64,
         circulating_supply: f64,
-    ) -> Result<(f64, f64, f64)> {
-        let start_time = Instant::now();
-        
+        victim_quote_mint: Option<&Pubkey>,
+    ) -> Result<McapImpact> {
         // Récupérer le prix SOL en parallèle
         let sol_price = self.get_sol_price_cached().await?;
+
+        // Écarter les pools aux réserves nulles (artefact du diff de balances) : la suivante
+        // plus dominante devient candidate au lieu de faire échouer toute l'analyse
+        let pools = if self.config.skip_zero_reserve_pools {
+            let before = pools.len();
+            let usable: Vec<PoolInfo> = pools
+                .into_iter()
+                .filter(|pool| pool.token_a_liquidity > 0.0 && pool.token_b_liquidity > 0.0)
+                .collect();
+            if usable.is_empty() {
+                return Err(AnalysisError::PoolParseFailed {
+                    reason: format!("réserves nulles sur les {} pools", before),
+                }.into());
+            }
+            if usable.len() < before {
+                log::debug!("🕳️ {} pool(s) aux réserves nulles ignorée(s)", before - usable.len());
+            }
+            usable
+        } else {
+            pools
+        };
+
+        // Ne garder que les pools cotées dans le mint payé par la cible : sinon l'impact serait calculé
+        // sur la mauvaise jambe (cible en USDC, pool dominante en SOL). Aucune pool correspondante
+        // (route multi-hop USDC → SOL → token) : toutes restent candidates.
+        let pools = match victim_quote_mint {
+            Some(victim_quote) => {
+                let (on_route, off_route): (Vec<PoolInfo>, Vec<PoolInfo>) = pools
+                    .into_iter()
+                    .partition(|pool| Self::pool_quote_mint(pool, token_mint) == *victim_quote);
+                if on_route.is_empty() {
+                    log::debug!("🔀 Aucune pool cotée en {} (mint payé par la cible) - route multi-hop, toutes les pools restent candidates", victim_quote);
+                    off_route
+                } else {
+                    if !off_route.is_empty() {
+                        log::debug!("🔀 {} pool(s) cotée(s) hors du mint payé par la cible ({}) ignorée(s)", off_route.len(), victim_quote);
+                    }
+                    on_route
+                }
+            }
+            None => pools,
+        };
         
         // 🎯 STRATÉGIE SANDWICH BOT : Pool dominante uniquement
         if pools.len() == 1 {
@@ -835,20 +1547,24 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             return self.calculate_mcap_impact_single_pool(&pools[0], token_mint, tokens_received, circulating_supply, sol_price).await;
         } else {
             // PLUSIEURS POOLS : Utiliser la pool dominante
-            let (dominant_pool, dominance_ratio) = self.find_dominant_pool(&pools, token_mint, sol_price)?;
+            let (dominant_pool, _dominance_ratio) = self.find_dominant_pool(&pools, token_mint, sol_price)?;
             
-            // Vérifier si la pool est bien parsée
-            if dominant_pool.reserve_a == 0 || dominant_pool.reserve_b == 0 {
-                return Err(anyhow!("Pool dominante mal parsée - réserves nulles"));
+            // Vérifier si la pool est bien parsée (liquidités UI : les réserves brutes des pools
+            // extraites sont des montants UI tronqués, 0 pour moins d'une unité de quote)
+            if dominant_pool.token_a_liquidity <= 0.0 || dominant_pool.token_b_liquidity <= 0.0 {
+                return Err(AnalysisError::PoolParseFailed {
+                    reason: format!("réserves nulles sur la pool dominante {}", dominant_pool.pool_id),
+                }.into());
             }
             
-            let result = self.calculate_mcap_impact_single_pool(&dominant_pool, token_mint, tokens_received, circulating_supply, sol_price).await?;
+            let result = self.calculate_mcap_impact_single_pool(dominant_pool, token_mint, tokens_received, circulating_supply, sol_price).await?;
             
             Ok(result)
         }
     }
 
     /// Calcule l'impact MCap avec UNE SEULE pool (méthode la plus précise)
+    #[tracing::instrument(level = "debug", name = "compute_impact", skip_all, fields(dex = ?pool.dex_type))]
     async fn calculate_mcap_impact_single_pool(
         &self,
         pool: &PoolInfo,
@@ -856,21 +1572,14 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         tokens_received: f64,
         circulating_supply: f64,
         sol_price: f64,
-    ) -> Result<(f64, f64, f64)> {
-        // Identifier les réserves de la pool
-        let (reserve_token, reserve_quote, is_sol_pair) = if pool.token_a_mint == *token_mint {
-            (
-                pool.reserve_a as f64,
-                pool.reserve_b as f64,
-                pool.token_b_mint.to_string() == WSOL_MINT
-            )
+    ) -> Result<McapImpact> {
+        // Identifier les réserves de la pool (unités UI, comme tokens_received)
+        let (reserve_token, reserve_quote, quote_mint) = if pool.token_a_mint == *token_mint {
+            (pool.token_a_liquidity, pool.token_b_liquidity, pool.token_b_mint)
         } else {
-            (
-                pool.reserve_b as f64,
-                pool.reserve_a as f64,
-                pool.token_a_mint.to_string() == WSOL_MINT
-            )
+            (pool.token_b_liquidity, pool.token_a_liquidity, pool.token_a_mint)
         };
+        let is_sol_pair = quote_mint.to_string() == WSOL_MINT;
         
         // Prix AVANT le swap
         let price_before_in_quote = reserve_quote / reserve_token;
@@ -880,10 +1589,22 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             price_before_in_quote
         };
         
-        // Calculer les nouvelles réserves APRÈS le swap (AMM: x × y = k)
-        let k = reserve_token * reserve_quote;
-        let reserve_token_after = reserve_token - tokens_received;
-        let reserve_quote_after = k / reserve_token_after;
+        // Calculer les nouvelles réserves APRÈS le swap (AMM: x × y = k, frais restant dans le pool)
+        let fee_bps = if self.config.fee_adjusted_impact { pool.fee_bps } else { 0 };
+        let (reserve_token_after, reserve_quote_after, quote_in_observed) = match pool.observed_quote_in {
+            // Quote entrée connue (diff des balances du pool) : partir de ce qui a réellement été payé,
+            // reserve_quote_after = reserve_quote + quote_in, et la réserve token en découle (k / reserve_quote_after sans frais)
+            Some(quote_in) if self.config.impact_from_observed_quote_in => {
+                let token_out = amm_out_with_fee(reserve_quote, reserve_token, quote_in, fee_bps);
+                (reserve_token - token_out, reserve_quote + quote_in, true)
+            }
+            // Sinon : quote entrée déduite des tokens reçus
+            _ => {
+                let quote_in = amm_in_with_fee(reserve_quote, reserve_token, tokens_received, fee_bps)
+                    .ok_or_else(|| anyhow!("Tokens reçus ({:.0}) >= réserve du pool ({:.0})", tokens_received, reserve_token))?;
+                (reserve_token - tokens_received, reserve_quote + quote_in, false)
+            }
+        };
         
         // Prix APRÈS le swap
         let price_after_in_quote = reserve_quote_after / reserve_token_after;
@@ -893,24 +1614,46 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             price_after_in_quote
         };
         
-        // MCap AVANT et APRÈS
+        // MCap AVANT et APRÈS (supply déjà en unités UI, comme les réserves)
         let mcap_before = price_before_usd * circulating_supply;
         let mcap_after = price_after_usd * circulating_supply;
         let mcap_impact_pct = ((mcap_after - mcap_before) / mcap_before) * 100.0;
         
-        Ok((mcap_before, mcap_after, mcap_impact_pct))
+        let reserve_share = tokens_received / reserve_token;
+
+        let debug = ImpactDebug {
+            pool_dex: pool.dex_type.clone(),
+            quote_mint,
+            reserve_token_before: reserve_token,
+            reserve_quote_before: reserve_quote,
+            reserve_token_after,
+            reserve_quote_after,
+            price_before: price_before_in_quote,
+            price_after: price_after_in_quote,
+            tokens_delta: reserve_token - reserve_token_after,
+            quote_in: reserve_quote_after - reserve_quote,
+            quote_in_observed,
+            fee_bps,
+            sol_price,
+            circulating_supply,
+        };
+        
+        Ok(McapImpact { mcap_before, mcap_impact_pct, quote_mint, reserve_share, debug })
     }
 
     /// Obtient le nom du DEX pour les logs
     fn get_dex_name(&self, dex_type: &crate::types::DexType) -> &'static str {
         match dex_type {
             crate::types::DexType::RaydiumV4 => "Raydium V4",
+            crate::types::DexType::RaydiumCLMM => "Raydium CLMM",
             crate::types::DexType::OrcaWhirlpool => "Orca Whirlpool",
             crate::types::DexType::MeteoraDLMM => "Meteora DLMM",
             crate::types::DexType::Lifinity => "Lifinity",
             crate::types::DexType::Phoenix => "Phoenix",
             crate::types::DexType::Serum => "Serum",
+            crate::types::DexType::OpenBookV2 => "OpenBook V2",
             crate::types::DexType::Jupiter => "Jupiter",
+            crate::types::DexType::PumpFun => "Pump.fun",
             crate::types::DexType::Unsupported => "DEX Non Supporté",
             crate::types::DexType::Unknown => "Unknown DEX",
         }
@@ -922,9 +1665,12 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         if let Some(dex_name) = is_known_dex_program(owner) {
             match dex_name {
                 "Raydium V4" => crate::types::DexType::RaydiumV4,
+                "Raydium CLMM" => crate::types::DexType::RaydiumCLMM,
                 "Orca Whirlpool" => crate::types::DexType::OrcaWhirlpool,
                 "Meteora DLMM" => crate::types::DexType::MeteoraDLMM,
                 "Jupiter V6" => crate::types::DexType::Jupiter,
+                "OpenBook V2" => crate::types::DexType::OpenBookV2,
+                "Pump.fun" | "Pump.fun Bonding Curve" => crate::types::DexType::PumpFun,
                 _ => crate::types::DexType::Unknown,
             }
         } else if let Some(pool_name) = is_known_pool_account(owner) {
@@ -953,36 +1699,30 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         sol_price: f64,
     ) -> Result<(&'a PoolInfo, f64)> {
         let mut max_liquidity = 0.0;
-        let mut dominant_pool = &pools[0];
+        let mut dominant_index = 0;
         let mut total_liquidity = 0.0;
+        let mut candidates = Vec::with_capacity(pools.len());
         
-        // Calculer la liquidité de chaque pool
-        for pool in pools {
-            let (_reserve_token, reserve_quote, is_sol_pair) = if pool.token_a_mint == *token_mint {
-                (
-                    pool.reserve_a as f64,
-                    pool.reserve_b as f64,
-                    pool.token_b_mint.to_string() == WSOL_MINT
-                )
+        // Calculer la liquidité de chaque pool (unités UI)
+        for (index, pool) in pools.iter().enumerate() {
+            let (reserve_token, reserve_quote, quote_mint) = if pool.token_a_mint == *token_mint {
+                (pool.token_a_liquidity, pool.token_b_liquidity, pool.token_b_mint)
             } else {
-                (
-                    pool.reserve_b as f64,
-                    pool.reserve_a as f64,
-                    pool.token_a_mint.to_string() == WSOL_MINT
-                )
+                (pool.token_b_liquidity, pool.token_a_liquidity, pool.token_a_mint)
             };
             
-            let liquidity_usd = if is_sol_pair {
+            let liquidity_usd = if quote_mint.to_string() == WSOL_MINT {
                 reserve_quote * sol_price * 2.0
                 } else {
                 reserve_quote * 2.0
             };
             
             total_liquidity += liquidity_usd;
+            candidates.push((pool, reserve_token, reserve_quote, quote_mint, liquidity_usd));
             
             if liquidity_usd > max_liquidity {
                 max_liquidity = liquidity_usd;
-                dominant_pool = pool;
+                dominant_index = index;
             }
         }
         
@@ -991,30 +1731,117 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         } else {
             0.0
         };
+
+        // Trace complète du choix, pour pouvoir expliquer une mauvaise sélection après coup
+        if self.config.log_pool_selection {
+            log::info!(
+                "🧭 Sélection pool pour {}: {} candidates, liquidité totale ${:.0}, retenue #{} (dominance {:.1}%)",
+                token_mint, pools.len(), total_liquidity, dominant_index, dominance_ratio * 100.0
+            );
+            for (index, (pool, reserve_token, reserve_quote, quote_mint, liquidity_usd)) in candidates.iter().enumerate() {
+                let share = if total_liquidity > 0.0 { liquidity_usd / total_liquidity * 100.0 } else { 0.0 };
+                log::info!(
+                    "   {} #{} {} | quote {} | réserves {:.2} token / {:.4} quote | liquidité ${:.0} ({:.1}%)",
+                    if index == dominant_index { "✅" } else { "  " },
+                    index, self.get_dex_name(&pool.dex_type), quote_mint,
+                    reserve_token, reserve_quote, liquidity_usd, share
+                );
+            }
+        }
         
-        Ok((dominant_pool, dominance_ratio))
+        Ok((&pools[dominant_index], dominance_ratio))
     }
 
 
 
     
+    /// Analyse un lot de signatures avec une concurrence bornée (résultats dans l'ordre d'entrée)
+    pub async fn analyze_signatures(
+        &self,
+        sigs: &[String],
+        concurrency: usize,
+    ) -> Vec<(String, Result<SandwichAnalysisResult>)> {
+        stream::iter(sigs)
+            .map(|signature| async move {
+                // Partage le même limiteur que le flux live
+                let _permit = self.analysis_limiter.acquire().await;
+                let result = self.analyze_transaction_for_sandwich(signature).await;
+                (signature.clone(), result)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
     /// Analyse une transaction pour détecter les opportunités de sandwich
-    async fn analyze_transaction_for_sandwich(&self, signature: &str) -> Result<SandwichAnalysisResult> {
+    #[tracing::instrument(level = "debug", name = "analyze_transaction", skip_all, fields(signature = %signature))]
+    pub async fn analyze_transaction_for_sandwich(&self, signature: &str) -> Result<SandwichAnalysisResult> {
 
         let start_time = Instant::now();
+        // Un seul budget pour toute l'analyse de cette signature
+        let deadline = self.new_deadline();
         
         // Analyser la transaction
-        let (tokens_received, mcap_before, mcap_impact_pct) = self
-            .calculate_tokens_received_and_mcap_impact(signature, 0.0)
-            .await?;
+        let swap = match self
+            .calculate_tokens_received_and_mcap_impact_within(signature, 0.0, &deadline)
+            .await
+        {
+            Ok(swap) => swap,
+            Err(e) => match e.downcast_ref::<AnalysisError>() {
+                // Classé à part plutôt que de signaler une opportunité fantôme
+                Some(AnalysisError::RoundTrip { .. }) => {
+                    return Ok(SandwichAnalysisResult {
+                        signature: signature.to_string(),
+                        invested_amount: 0.0,
+                        tokens_received: 0.0,
+                        mcap_before: 0.0,
+                        mcap_after: 0.0,
+                        mcap_impact: 0.0,
+                        execution_time: start_time.elapsed(),
+                        is_sandwich_opportunity: false,
+                        estimated_profit: 0.0,
+                        classification: TransactionClassification::RoundTrip,
+                        tokens_received_estimated: false,
+                        parties: None,
+                        debug_detail: None,
+                    });
+                }
+                _ => return Err(e),
+            },
+        };
 
         let execution_time = start_time.elapsed();
         
         // Calculer le montant investi
-        let invested_amount = self.get_investment_value_fast(signature).await?;
+        let invested_amount = self.get_investment_value_within(signature, Some(&swap.quote_mint), &deadline).await?;
+        let VictimSwapAnalysis {
+            tokens_received, mcap_before, mcap_impact_pct, tokens_received_estimated, reserve_share, parties, impact_debug, ..
+        } = swap;
+
+        let filter = &self.config.opportunity_filter;
+
+        // Tokens reçus et réserve du pool doivent rester cohérents
+        let classification = if reserve_share > self.config.max_received_reserve_share {
+            log::warn!(
+                "🤨 TX: {} | Tokens reçus = {:.0}% de la réserve du pool - détection suspecte, impact non retenu",
+                signature, reserve_share * 100.0
+            );
+            TransactionClassification::ImplausibleSize
+        } else if mcap_impact_pct > filter.max_mcap_impact_pct {
+            log::warn!(
+                "🤨 TX: {} | Impact {:.1}% > max {:.1}% - rejeté | Entrées: tokens reçus {:.2}, part réserve {:.2}%, MCap avant ${:.0}, investi ${:.2}",
+                signature, mcap_impact_pct, filter.max_mcap_impact_pct,
+                tokens_received, reserve_share * 100.0, mcap_before, invested_amount
+            );
+            TransactionClassification::ImplausibleImpact
+        } else {
+            TransactionClassification::Swap
+        };
         
         // Déterminer si c'est une opportunité de sandwich
-        let is_sandwich_opportunity = mcap_impact_pct > 2.0 && invested_amount > 100.0;
+        let is_sandwich_opportunity = classification == TransactionClassification::Swap
+            && mcap_impact_pct > filter.min_mcap_impact_pct
+            && invested_amount > filter.min_invested_usd;
 
         let estimated_profit = if is_sandwich_opportunity {
             invested_amount * 0.05 // Estimation 5% de profit
@@ -1032,11 +1859,68 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             execution_time,
             is_sandwich_opportunity,
             estimated_profit,
+            classification,
+            tokens_received_estimated,
+            parties: Some(parties),
+            debug_detail: self.config.verbose_impact_detail.then_some(impact_debug),
         })
     }
 
 
 
+    /// Rejoue l'analyse complète d'une transaction sauvegardée en JSON (ex. dump de `dump_failed_tx_to`),
+    /// sans RPC : prix SOL fixe (`offline_sol_price`) et supplies pré-remplies (`offline_supplies`).
+    /// Les tokens encore sur une bonding curve Pump.fun sont analysés via les pools de la transaction.
+    pub async fn analyze_file(&self, path: &Path) -> Result<SandwichAnalysisResult> {
+        let json = tokio::fs::read(path).await
+            .map_err(|e| anyhow!("Lecture de {} impossible: {}", path.display(), e))?;
+        let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_slice(&json)
+            .map_err(|e| anyhow!("JSON de transaction invalide dans {}: {}", path.display(), e))?;
+
+        // Signature de la transaction, sinon le nom du fichier (`<signature>.json` pour les dumps)
+        let signature = match &tx.transaction.transaction {
+            solana_transaction_status::EncodedTransaction::Json(ui_tx) => ui_tx.signatures.first().cloned(),
+            _ => None,
+        }
+        .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .ok_or_else(|| anyhow!("Signature introuvable pour {}", path.display()))?;
+
+        let supplies = self.config.offline_supplies.iter()
+            .map(|(mint, supply)| (*mint, (*supply, Instant::now())))
+            .collect();
+
+        let mut engine = self.clone_for_async();
+        engine.sol_price = Arc::new(RwLock::new(Some(self.config.offline_sol_price)));
+        engine.supply_cache = Arc::new(RwLock::new(supplies));
+        engine.offline_transactions = Some(Arc::new(AHashMap::from_iter([(signature.clone(), tx)])));
+
+        log::info!("💾 Analyse hors-ligne de {} ({})", signature, path.display());
+        engine.analyze_transaction_for_sandwich(&signature).await
+    }
+
+    /// Rejoue l'analyse (RPC, sans WebSocket) des signatures d'un fichier, une par ligne (lignes vides et
+    /// `#` ignorées), avec `max_concurrent_analyses` analyses en parallèle. Résultats dans l'ordre du
+    /// fichier ; les signatures en erreur sont loggées et omises.
+    pub async fn analyze_signatures_from_file(&self, path: &Path) -> Result<Vec<SandwichAnalysisResult>> {
+        let text = tokio::fs::read_to_string(path).await
+            .map_err(|e| anyhow!("Lecture de {} impossible: {}", path.display(), e))?;
+        let sigs: Vec<String> = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+
+        log::info!("🔁 Rejeu de {} signature(s) depuis {}", sigs.len(), path.display());
+        let mut results = Vec::with_capacity(sigs.len());
+        for (signature, result) in self.analyze_signatures(&sigs, self.config.max_concurrent_analyses).await {
+            match result {
+                Ok(result) => results.push(result),
+                Err(e) => log::info!("❌ TX: {} | Erreur: {}", signature, e),
+            }
+        }
+        Ok(results)
+    }
+
     /// Initialise la connexion WebSocket (ne fait que la connexion)
     pub async fn initialize_websocket(&self) -> Result<()> {
 
@@ -1068,19 +1952,104 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
         }
     }
 
+    /// Traite les logs WebSocket et se réabonne quand le récepteur se ferme (connexion perdue),
+    /// avec un backoff exponentiel plafonné à `websocket_reconnect_max_backoff_secs`
+    async fn run_websocket_with_reconnect(
+        self,
+        mut logs_receiver: crossbeam_channel::Receiver<Response<RpcLogsResponse>>,
+        tx_sender: mpsc::UnboundedSender<QueuedTransaction>,
+    ) {
+        let max_backoff = Duration::from_secs(self.config.websocket_reconnect_max_backoff_secs.max(1));
+        let mut backoff = Duration::from_secs(1);
+        let mut consecutive_failures: u32 = 0;
+
+        loop {
+            log::info!("🚀 Lancement du traitement des logs WebSocket...");
+            let session_start = Instant::now();
+            Self::process_websocket_logs(
+                logs_receiver,
+                tx_sender.clone(),
+                Arc::clone(&self.config),
+                Arc::clone(&self.async_rpc),
+                Arc::clone(&self.seen_signatures),
+            ).await;
+            log::warn!("⚠️ Le traitement des logs WebSocket s'est arrêté (connexion perdue après {:.0}s)", session_start.elapsed().as_secs_f64());
+
+            // Connexion restée stable assez longtemps : repartir du backoff minimal
+            if session_start.elapsed() > max_backoff {
+                backoff = Duration::from_secs(1);
+            }
+
+            logs_receiver = loop {
+                log::info!("🔌 Reconnexion WebSocket dans {}s...", backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+
+                match self.initialize_websocket().await {
+                    Ok(()) => {
+                        let receiver = self.logs_receiver.write().await.take();
+                        if let Some(receiver) = receiver {
+                            log::info!("✅ WebSocket reconnecté après {} échec(s)", consecutive_failures);
+                            consecutive_failures = 0;
+                            break receiver;
+                        }
+                    }
+                    Err(e) => {
+                        consecutive_failures += 1;
+                        log::warn!("❌ Reconnexion WebSocket échouée ({} échec(s) consécutif(s)): {}", consecutive_failures, e);
+                    }
+                }
+            };
+        }
+    }
+
     /// Traite les logs de transaction reçus via WebSocket
     async fn process_websocket_logs(
         logs_receiver: crossbeam_channel::Receiver<Response<RpcLogsResponse>>,
-        tx_sender: mpsc::UnboundedSender<(String, EncodedConfirmedTransactionWithStatusMeta)>,
+        tx_sender: mpsc::UnboundedSender<QueuedTransaction>,
+        config: Arc<BotConfig>,
+        async_rpc: Arc<AsyncRpcClient>,
+        seen_signatures: Arc<std::sync::Mutex<SeenSignatures>>,
     ) {
+        let report_every = Duration::from_secs(config.seen_signatures_report_secs);
+        let mut last_report = Instant::now();
+
+        // Le recv() crossbeam est bloquant : l'attendre sur un thread dédié et relayer les logs par un canal
+        // tokio, pour ne pas immobiliser un worker du runtime entre deux notifications
+        let (logs_sender, mut logs_stream) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            while let Ok(logs) = logs_receiver.recv() {
+                if logs_sender.send(logs).is_err() {
+                    break;
+                }
+            }
+        });
 
-        let mut last_processed_time = std::time::Instant::now();
-
-        while let Ok(logs) = logs_receiver.recv() {
+        while let Some(logs) = logs_stream.recv().await {
+            metrics::TRANSACTIONS_RECEIVED.inc();
             // Filtrer les transactions DEX intéressantes
-            if Self::is_dex_transaction(&logs) {
+            if Self::is_dex_transaction(&logs, config.analyze_pumpfun) {
+                // Même signature reçue plusieurs fois (reconnexion, plusieurs programmes mentionnés...)
+                let (duplicate, stats) = {
+                    let mut seen = seen_signatures.lock().unwrap_or_else(|e| e.into_inner());
+                    (seen.check_and_insert(&logs.value.signature), seen.stats())
+                };
+                if !report_every.is_zero() && last_report.elapsed() >= report_every {
+                    last_report = Instant::now();
+                    log::info!(
+                        "🧾 Signatures vues: {}/{} | Doublons: {} / {} ({:.2}%) | Évictions avant expiration: {}",
+                        stats.len, stats.capacity, stats.hits, stats.lookups,
+                        stats.hit_rate() * 100.0, stats.capacity_evictions
+                    );
+                }
+                if duplicate {
+                    log::debug!("♻️ TX: {} | Ignorée: signature déjà reçue", logs.value.signature);
+                    continue;
+                }
+                metrics::DEX_TRANSACTIONS_MATCHED.inc();
+
                 // Déterminer le type de DEX pour les logs
-                let dex_type = Self::get_dex_type_from_logs(&logs);
+                let _dex_type = Self::get_dex_type_from_logs(&logs);
                 //log::info!("🎯 Transaction {} détectée: {}", dex_type, logs.value.signature);
                 
                 // Analyser toutes les transactions DEX immédiatement
@@ -1089,30 +2058,35 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
                 // Démarrer l'analyse en parallèle
                 let signature = logs.value.signature.clone();
                 let sender_clone = tx_sender.clone();
+                let skip_non_swap_transactions = config.skip_non_swap_transactions;
+                let encoding = config.transaction_encoding;
+                let async_rpc = Arc::clone(&async_rpc);
                 
                 tokio::spawn(async move {
 
                     // Récupérer les détails de la transaction
-                if let Ok(tx_data) = Self::fetch_transaction_details(&signature).await {
-                    if let Err(e) = sender_clone.send((signature.clone(), tx_data)) {
+                if let Ok(tx_data) = Self::fetch_transaction_details(&async_rpc, &signature, encoding).await {
+                    // Pré-filtre : pas de mouvement de token = pas de swap (compute budget, setup...)
+                    if skip_non_swap_transactions && !Self::has_token_balance_changes(&tx_data) {
+                        log::debug!("⏭️ TX: {} | Ignorée: aucun changement de balance de token", signature);
+                        return;
                     }
+                    let _ = sender_clone.send((signature.clone(), tx_data, Instant::now()));
                 }
 
                 });
-                
-                // Mettre à jour le timer
-                last_processed_time = std::time::Instant::now();
             }
         }
     }
 
     /// Vérifie si une transaction est une transaction DEX intéressante
-    fn is_dex_transaction(logs: &Response<RpcLogsResponse>) -> bool {
+    fn is_dex_transaction(logs: &Response<RpcLogsResponse>, include_pumpfun: bool) -> bool {
         // Programmes DEX principaux à surveiller
         const DEX_PROGRAMS: &[&str] = &[
             // Raydium (gros volumes)
             "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", // Raydium V4
             "RVKd61ztZW9GUwhRbbLoYVRE5Xf1B2tVscKqwZqXgEr", // Raydium V3
+            RAYDIUM_CLMM_PROGRAM,                            // Raydium CLMM
             
             // Orca (gros volumes)
             "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc", // Orca Whirlpool
@@ -1127,11 +2101,37 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             
             // Serum (legacy mais encore actif)
             "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin", // Serum DEX V3
+
+            // OpenBook v2 (successeur de Serum)
+            OPENBOOK_V2_PROGRAM,
         ];
         
         // Vérifier si les logs contiennent des références à un programme DEX
         logs.value.logs.iter().any(|log| {
             DEX_PROGRAMS.iter().any(|&program_id| log.contains(program_id))
+                || (include_pumpfun && log.contains(PUMPFUN_PROGRAM))
+        })
+    }
+
+    /// Vérifie qu'au moins une balance de token a bougé dans la transaction
+    fn has_token_balance_changes(tx: &EncodedConfirmedTransactionWithStatusMeta) -> bool {
+        let Some(meta) = tx.transaction.meta.as_ref() else {
+            return false;
+        };
+        let (
+            solana_transaction_status::option_serializer::OptionSerializer::Some(pre),
+            solana_transaction_status::option_serializer::OptionSerializer::Some(post)
+        ) = (&meta.pre_token_balances, &meta.post_token_balances) else {
+            return false;
+        };
+
+        let pre_amounts: AHashMap<u8, &str> = pre.iter()
+            .map(|b| (b.account_index, b.ui_token_amount.amount.as_str()))
+            .collect();
+
+        // Un compte créé pendant la tx (absent des pre balances) compte comme un changement
+        post.len() != pre.len() || post.iter().any(|b| {
+            pre_amounts.get(&b.account_index) != Some(&b.ui_token_amount.amount.as_str())
         })
     }
 
@@ -1139,7 +2139,8 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
     fn get_dex_type_from_logs(logs: &Response<RpcLogsResponse>) -> &'static str {
         for log in &logs.value.logs {
             if log.contains("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8") || 
-               log.contains("RVKd61ztZW9GUwhRbbLoYVRE5Xf1B2tVscKqwZqXgEr") {
+               log.contains("RVKd61ztZW9GUwhRbbLoYVRE5Xf1B2tVscKqwZqXgEr") ||
+               log.contains(RAYDIUM_CLMM_PROGRAM) {
                 return "Raydium";
             }
             if log.contains("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc") || 
@@ -1156,43 +2157,65 @@ let tx_result = match timeout(Duration::from_secs(5), self.async_rpc.get_transac
             if log.contains("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin") {
                 return "Serum";
             }
+            if log.contains(OPENBOOK_V2_PROGRAM) {
+                return "OpenBook V2";
+            }
+            if log.contains(PUMPFUN_PROGRAM) {
+                return "Pump.fun";
+            }
         }
         "Unknown DEX"
     }
 
 
-    /// Récupère les détails d'une transaction spécifique
-    async fn fetch_transaction_details(signature: &str) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
-        let rpc_url = std::env::var("RPC_URL")
-            .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
-        let rpc_client = solana_client::rpc_client::RpcClient::new(rpc_url);
+    /// Écrit le JSON complet d'une transaction dont l'analyse a échoué (`<dir>/<signature>.json`)
+    async fn dump_failed_transaction(
+        dir: &Path,
+        signature: &str,
+        tx_data: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        let json = serde_json::to_vec_pretty(tx_data)?;
+        tokio::fs::write(dir.join(format!("{}.json", signature)), json).await?;
+        Ok(())
+    }
+
+    /// Récupère les détails d'une transaction spécifique (client RPC partagé, construit depuis `config.rpc_url`),
+    /// dans l'encodage `config.transaction_encoding` de l'analyse
+    async fn fetch_transaction_details(
+        rpc: &AsyncRpcClient,
+        signature: &str,
+        encoding: UiTransactionEncoding,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
         let sig = Signature::from_str(signature)?;
+        let config = |commitment| solana_client::rpc_config::RpcTransactionConfig {
+            encoding: Some(encoding),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        };
 
         // Essayer d'abord avec "processed" pour les transactions en cours
-        match rpc_client.get_transaction_with_config(
-            &sig,
-            solana_client::rpc_config::RpcTransactionConfig {
-                encoding: Some(UiTransactionEncoding::Json),
-                commitment: Some(CommitmentConfig::processed()),
-                max_supported_transaction_version: Some(0),
-            },
-        ) {
+        match rpc.get_transaction_with_config(&sig, config(CommitmentConfig::processed())).await {
             Ok(tx) => Ok(tx),
-            Err(_) => {
-                // Si pas trouvée avec "processed", essayer avec "confirmed"
-                rpc_client.get_transaction_with_config(
-                    &sig,
-                    solana_client::rpc_config::RpcTransactionConfig {
-                        encoding: Some(UiTransactionEncoding::Json),
-                        commitment: Some(CommitmentConfig::confirmed()),
-                        max_supported_transaction_version: Some(0),
-                    },
-                ).map_err(|e| anyhow!("Erreur lors de la récupération de la transaction: {}", e))
-            }
+            // Si pas trouvée avec "processed", essayer avec "confirmed"
+            Err(_) => rpc.get_transaction_with_config(&sig, config(CommitmentConfig::confirmed())).await
+                .map_err(|e| anyhow!("Erreur lors de la récupération de la transaction: {}", e)),
         }
     }
 
 
+/// Abandonne une transaction en file depuis plus de `max_queue_age_ms` : garder le budget RPC pour les fraîches
+fn drop_if_stale(&self, signature: &str, enqueued_at: Instant) -> bool {
+    let max_age_ms = self.config.max_queue_age_ms;
+    let age_ms = enqueued_at.elapsed().as_millis() as u64;
+    if max_age_ms == 0 || age_ms <= max_age_ms {
+        return false;
+    }
+    metrics::STALE_DROPS.inc();
+    log::debug!("🗑️ TX: {} | Ignorée: en attente depuis {}ms (> {}ms) | {} abandons", signature, age_ms, max_age_ms, metrics::STALE_DROPS.get());
+    true
+}
+
 pub async fn monitor_websocket_transactions(&mut self) -> Result<()> {
     // Créer un canal pour recevoir les transactions traitées
     let (tx_sender, mut tx_receiver) = mpsc::unbounded_channel();
@@ -1205,30 +2228,53 @@ pub async fn monitor_websocket_transactions(&mut self) -> Result<()> {
             .ok_or_else(|| anyhow!("Récepteur de logs non initialisé"))?
     };
 
-    // Démarrer le traitement des logs
-    let sender_clone = tx_sender.clone();
-    tokio::spawn(async move {
-        log::info!("🚀 Lancement du traitement des logs WebSocket...");
-        Self::process_websocket_logs(logs_receiver, sender_clone).await;
-        log::warn!("⚠️ Le traitement des logs WebSocket s'est arrêté !");
+    // Export CSV optionnel, partagé par toutes les tâches d'analyse
+    let recorder = self.config.analysis_csv_path.clone().map(|path| {
+        log::info!("🗃️ Export CSV des analyses: {}", path.display());
+        Arc::new(std::sync::Mutex::new(AnalysisRecorder::new(path, self.config.analysis_csv_flush_every)))
     });
 
+    // Démarrer le traitement des logs (avec reconnexion automatique si la connexion tombe)
+    let engine = self.clone_for_async();
+    tokio::spawn(engine.run_websocket_with_reconnect(logs_receiver, tx_sender.clone()));
+
     // Boucle principale : écoute des transactions envoyées depuis process_websocket_logs
-    let mut transaction_count = 0;
     log::info!("📥 En attente de transactions...");
 
-    while let Some((signature, tx_data)) = tx_receiver.recv().await {
-        transaction_count += 1;
-
+    while let Some((signature, tx_data, enqueued_at)) = tx_receiver.recv().await {
         let monitoring_engine = self.clone_for_async();
+        let recorder = recorder.clone();
         let signature_clone = signature.clone();
+        // Trop vieille pour valoir un appel RPC : ne pas attendre de place pour elle
+        if self.drop_if_stale(&signature, enqueued_at) {
+            continue;
+        }
+        // Attendre une place libre avant de lancer l'analyse (l'attente peut elle-même la rendre trop vieille)
+        let permit = Arc::clone(&self.analysis_limiter).acquire_owned().await?;
+        if self.drop_if_stale(&signature, enqueued_at) {
+            continue;
+        }
 
         tokio::spawn(async move {
+            let _permit = permit;
             let start = std::time::Instant::now();
-            match monitoring_engine.analyze_transaction_for_sandwich(&signature_clone).await {
+            let analysis = monitoring_engine.analyze_transaction_for_sandwich(&signature_clone).await;
+            metrics::ANALYSIS_LATENCY.observe(start.elapsed().as_secs_f64());
+            match analysis {
                 Ok(result) => {
                     let elapsed = start.elapsed().as_millis();
                     if result.is_sandwich_opportunity {
+                        metrics::OPPORTUNITIES_DETECTED.inc();
+                    }
+                    if let Some(recorder) = &recorder {
+                        let mut recorder = recorder.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Err(e) = recorder.record(&result) {
+                            log::warn!("⚠️ Export CSV de {} impossible: {}", result.signature, e);
+                        }
+                    }
+                    if result.classification == TransactionClassification::RoundTrip {
+                        log::info!("🔁 TX: {} | Type: Aller-retour/arbitrage | Temps: {}ms", result.signature, elapsed);
+                    } else if result.is_sandwich_opportunity {
                         log::info!(
                             "🚨 TX: {} | Investi: ${:.2} | MCap Avant: ${:.0} | MCap Après: ${:.0} | Impact: {:.2}% | Temps: {}ms",
                             result.signature, result.invested_amount,
@@ -1246,13 +2292,25 @@ pub async fn monitor_websocket_transactions(&mut self) -> Result<()> {
                 }
                 Err(e) => {
                     let elapsed = start.elapsed().as_millis();
-                    let msg = e.to_string();
-                    if msg.contains("Aucun token non-système reçu détecté") {
-                        //log::info!("🔄 TX: {} | Type: Arbitrage/Conversion SOL/USD$ | Temps: {}ms", signature_clone, elapsed);
-                    } else if msg.contains("Aucune pool DEX détectée") {
-                        log::info!("🏊 TX: {} | Type: Swap sans pool DEX détectée | Temps: {}ms", signature_clone, elapsed);
-                    } else {
-                        log::info!("❌ TX: {} | Erreur: {} | Temps: {}ms", signature_clone, msg, elapsed);
+                    metrics::ANALYSIS_ERRORS.with_label_values(&[metrics::error_kind(&e)]).inc();
+                    if let Some(dir) = &monitoring_engine.config.dump_failed_tx_to {
+                        if let Err(dump_err) = Self::dump_failed_transaction(dir, &signature_clone, &tx_data).await {
+                            log::warn!("⚠️ Impossible d'écrire la transaction {}: {}", signature_clone, dump_err);
+                        }
+                    }
+                    match e.downcast_ref::<AnalysisError>() {
+                        Some(AnalysisError::OwnTransaction) => {
+                            log::debug!("🙈 TX: {} | Ignorée: transaction de notre wallet", signature_clone);
+                        }
+                        Some(AnalysisError::NoNonSystemToken | AnalysisError::SellSwap { .. }) => {
+                            //log::info!("🔄 TX: {} | Type: Arbitrage/Conversion SOL/USD$ | Temps: {}ms", signature_clone, elapsed);
+                        }
+                        Some(AnalysisError::NoDexPool) => {
+                            log::info!("🏊 TX: {} | Type: Swap sans pool DEX détectée | Temps: {}ms", signature_clone, elapsed);
+                        }
+                        _ => {
+                            log::info!("❌ TX: {} | Erreur: {} | Temps: {}ms", signature_clone, e, elapsed);
+                        }
                     }
                 }
             }
@@ -1264,3 +2322,576 @@ pub async fn monitor_websocket_transactions(&mut self) -> Result<()> {
 
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        mint_account, parsed_transaction, parsed_transaction_json, pool_info, program_account, pyth_price_update_account, raydium_v4_account_data,
+        test_config, token_account, token_balance, usdc, wsol, FakeRpc,
+    };
+    use solana_transaction_status::UiTransactionTokenBalance;
+
+    fn engine(rpc: &FakeRpc) -> MonitoringEngine {
+        MonitoringEngine::new(
+            Arc::new(test_config()),
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            rpc.client(),
+            Arc::new(RwLock::new(AHashMap::new())),
+            AHashMap::new(),
+            Arc::new(RwLock::new(AHashMap::new())),
+        )
+    }
+
+    #[tokio::test]
+    async fn six_decimal_supply_gives_mcap_of_price_times_ui_supply() {
+        let rpc = FakeRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000)); // 1 000 000 tokens
+        let engine = engine(&rpc);
+
+        let deadline = Deadline::after(Duration::from_secs(5));
+        let supply = engine.get_circulating_supply(&token, &deadline).await.unwrap();
+        assert_eq!(supply, 1_000_000.0);
+
+        // 4 000 tokens pour 1 000 USDC : 0,25 $ le token
+        let pool = pool_info(token, usdc(), 4_000_000_000, 1_000_000_000, 6, 6);
+        let impact = engine.calculate_mcap_impact_single_pool(&pool, &token, 1.0, supply, 150.0).await.unwrap();
+        assert!((impact.mcap_before - 0.25 * 1_000_000.0).abs() < 1e-6, "mcap {}", impact.mcap_before);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stale_queued_transactions_are_dropped_and_counted() {
+        let engine = engine(&FakeRpc::new());
+        let enqueued_at = Instant::now();
+        assert!(!engine.drop_if_stale("fraiche", enqueued_at));
+
+        tokio::time::advance(Duration::from_millis(engine.config.max_queue_age_ms + 1)).await;
+        let before = metrics::STALE_DROPS.get();
+        assert!(engine.drop_if_stale("vieille", enqueued_at));
+        assert_eq!(metrics::STALE_DROPS.get(), before + 1);
+    }
+
+    #[test]
+    fn pool_seen_through_authority_and_market_owners_is_counted_once() {
+        let engine = engine(&FakeRpc::new());
+        let token = Pubkey::new_unique();
+        let (authority, market) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        // Mêmes réserves vues via la vault authority et via le market de la pool
+        let pre = vec![
+            token_balance(1, &token, &authority, 1_000_000.0, 6),
+            token_balance(2, &wsol(), &authority, 500.0, 9),
+            token_balance(3, &token, &market, 1_000_000.0, 6),
+            token_balance(4, &wsol(), &market, 500.0, 9),
+        ];
+        let post = vec![
+            token_balance(1, &token, &authority, 990_000.0, 6),
+            token_balance(2, &wsol(), &authority, 505.0, 9),
+            token_balance(3, &token, &market, 990_000.0, 6),
+            token_balance(4, &wsol(), &market, 505.0, 9),
+        ];
+
+        let extracted = engine.extract_pools_from_balances(&pre, &post, &[authority, market], &token).unwrap();
+        assert_eq!(extracted.len(), 2);
+
+        let pools = MonitoringEngine::dedup_pools(extracted);
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].token_b_liquidity, 500.0);
+        assert_eq!(pools[0].observed_quote_in, Some(5.0));
+    }
+
+    #[tokio::test]
+    async fn dominant_pool_with_less_than_one_quote_unit_is_usable() {
+        let engine = engine(&FakeRpc::new());
+        *engine.sol_price.write().await = Some(150.0);
+        let token = Pubkey::new_unique();
+        // Pools extraites des balances : 0,8 SOL de réserve, soit reserve_b = 0 une fois tronquée
+        let mut dominant = pool_info(token, wsol(), 0, 0, 6, 9);
+        dominant.token_a_liquidity = 10_000.0;
+        dominant.token_b_liquidity = 0.8;
+        let mut minor = dominant.clone();
+        minor.pool_id = Pubkey::new_unique();
+        minor.token_a_liquidity = 1_000.0;
+        minor.token_b_liquidity = 0.05;
+
+        let impact = engine
+            .calculate_mcap_impact_with_extracted_pools(vec![minor, dominant], &token, 10.0, 1_000_000.0, None)
+            .await
+            .unwrap();
+        assert_eq!(impact.debug.reserve_quote_before, 0.8);
+    }
+
+    #[test]
+    fn first_account_key_is_not_assumed_to_be_the_signer() {
+        let engine = engine(&FakeRpc::new());
+        let (program, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = parsed_transaction(&[(program, false), (user, true)], &[], &[]);
+
+        let parties = engine.extract_transaction_parties(&tx).unwrap();
+        assert_eq!(parties.fee_payer, user.to_string());
+        assert_eq!(engine.extract_user_owner_from_transaction(&tx).unwrap(), user.to_string());
+    }
+
+    #[test]
+    fn relayed_transaction_attributes_the_swap_to_the_signer_moving_tokens() {
+        let engine = engine(&FakeRpc::new());
+        let (relayer, user, token) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (relayer_str, user_str) = (relayer.to_string(), user.to_string());
+        // Le relayeur paie les frais (premier signataire), l'utilisateur reçoit les tokens
+        let tx = parsed_transaction(
+            &[(relayer, true), (user, true)],
+            &[token_balance(2, &token, &user_str, 0.0, 6), token_balance(3, &token, &relayer_str, 10.0, 6)],
+            &[token_balance(2, &token, &user_str, 5_000.0, 6), token_balance(3, &token, &relayer_str, 10.0, 6)],
+        );
+
+        let parties = engine.extract_transaction_parties(&tx).unwrap();
+        assert_eq!(parties.fee_payer, relayer_str);
+        assert_eq!(parties.swapper, user_str);
+
+        let mut config = test_config();
+        config.resolve_swapper_from_balances = false;
+        let engine = MonitoringEngine { config: Arc::new(config), ..engine };
+        assert_eq!(engine.extract_transaction_parties(&tx).unwrap().swapper, relayer_str);
+    }
+
+    #[tokio::test]
+    async fn observed_quote_in_and_inferred_quote_in_formulations() {
+        let token = Pubkey::new_unique();
+        // 1 000 000 tokens face à 100 USDC, 10 USDC entrés, sans frais
+        let mut pool = pool_info(token, usdc(), 1_000_000_000_000, 100_000_000, 6, 6);
+        pool.observed_quote_in = Some(10.0);
+        let exact_out = 1_000_000.0 * 10.0 / 110.0;
+
+        let with_flag = |observed: bool| {
+            let mut config = test_config();
+            config.fee_adjusted_impact = false;
+            config.impact_from_observed_quote_in = observed;
+            MonitoringEngine { config: Arc::new(config), ..engine(&FakeRpc::new()) }
+        };
+        let (observed, inferred) = (with_flag(true), with_flag(false));
+
+        // Tokens reçus cohérents avec la quote entrée : les deux formulations coïncident
+        let a = observed.calculate_mcap_impact_single_pool(&pool, &token, exact_out, 1e6, 150.0).await.unwrap();
+        let b = inferred.calculate_mcap_impact_single_pool(&pool, &token, exact_out, 1e6, 150.0).await.unwrap();
+        assert!(a.debug.quote_in_observed && !b.debug.quote_in_observed);
+        assert!((a.mcap_impact_pct - b.mcap_impact_pct).abs() < 1e-9);
+        assert!((a.mcap_impact_pct - 21.0).abs() < 1e-9, "impact {}", a.mcap_impact_pct);
+
+        // Moins de tokens reçus (taxe de transfert) : seule la formulation observée garde la quote réellement payée
+        let a = observed.calculate_mcap_impact_single_pool(&pool, &token, 80_000.0, 1e6, 150.0).await.unwrap();
+        let b = inferred.calculate_mcap_impact_single_pool(&pool, &token, 80_000.0, 1e6, 150.0).await.unwrap();
+        assert!((a.debug.quote_in - 10.0).abs() < 1e-9);
+        assert!((a.debug.reserve_token_after - 1_000_000.0 * 100.0 / 110.0).abs() < 1e-6);
+        assert!((b.debug.quote_in - 100.0 * 80_000.0 / 920_000.0).abs() < 1e-9);
+        assert!((b.debug.reserve_token_after - 920_000.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn websocket_logs_are_awaited_without_blocking_the_runtime() {
+        let engine = engine(&FakeRpc::new());
+        let (logs_sender, logs_receiver) = crossbeam_channel::unbounded();
+        let (tx_sender, _tx_receiver) = mpsc::unbounded_channel();
+        let processing = tokio::spawn(MonitoringEngine::process_websocket_logs(
+            logs_receiver,
+            tx_sender,
+            Arc::clone(&engine.config),
+            Arc::clone(&engine.async_rpc),
+            Arc::clone(&engine.seen_signatures),
+        ));
+
+        // Runtime de test mono-thread : un recv() bloquant dans la tâche de traitement figerait ce sleep
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        logs_sender.send(Response {
+            context: solana_client::rpc_response::RpcResponseContext { slot: 1, api_version: None },
+            value: RpcLogsResponse {
+                signature: "sig-1".to_string(),
+                err: None,
+                logs: vec![format!("Program {} invoke [1]", RAYDIUM_V4_PROGRAM)],
+            },
+        }).unwrap();
+        drop(logs_sender);
+
+        tokio::time::timeout(Duration::from_secs(5), processing).await.unwrap().unwrap();
+        assert!(engine.seen_signatures.lock().unwrap().check_and_insert("sig-1"));
+    }
+
+    #[tokio::test]
+    async fn bought_token_is_the_largest_user_increase_within_bounds() {
+        let engine = engine(&FakeRpc::new());
+        let user = Pubkey::new_unique().to_string();
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort_by_key(|mint| mint.to_string());
+        let [small, large, sold, huge] = mints;
+        let balances = |amounts: [f64; 6]| {
+            vec![
+                token_balance(1, &wsol(), &user, amounts[0], 9),
+                token_balance(2, &small, &user, amounts[1], 6),
+                token_balance(3, &large, &user, amounts[2], 6),
+                // Deuxième compte du même mint : les variations sont sommées
+                token_balance(4, &large, &user, amounts[3], 6),
+                token_balance(5, &sold, &user, amounts[4], 6),
+                token_balance(6, &huge, &user, amounts[5], 6),
+            ]
+        };
+        let analyze = |pre: Vec<UiTransactionTokenBalance>, post: Vec<UiTransactionTokenBalance>| {
+            let engine = &engine;
+            let user = user.clone();
+            async move { engine.analyze_tokens_from_pre_post_balances(&pre, &post, &user).await }
+        };
+
+        // +500, +1 500 réparti sur deux comptes, -3 000, +1e12 (hors max_tokens_received), WSOL ignoré
+        let pre = balances([10.0, 0.0, 100.0, 0.0, 3_000.0, 0.0]);
+        let post = balances([1.0, 500.0, 600.0, 1_000.0, 0.0, 1e12]);
+        let (mint, amount, direction) = analyze(pre.clone(), post.clone()).await.unwrap();
+        assert_eq!((mint, amount, direction), (large, 1_500.0, SwapDirection::Buy));
+
+        // Égalité : départagée par l'adresse du mint, quel que soit l'ordre des balances
+        let tie = balances([10.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        let tie_post = balances([9.0, 700.0, 700.0, 0.0, 0.0, 0.0]);
+        for _ in 0..2 {
+            let (mint, _, _) = analyze(tie.clone(), tie_post.clone()).await.unwrap();
+            assert_eq!(mint, small);
+            let reversed: Vec<_> = tie_post.iter().rev().cloned().collect();
+            let (mint, _, _) = analyze(tie.iter().rev().cloned().collect(), reversed).await.unwrap();
+            assert_eq!(mint, small);
+        }
+
+        // Aucune hausse : la plus forte baisse est une vente
+        let pre = balances([1.0, 500.0, 600.0, 1_000.0, 3_000.0, 0.0]);
+        let post = balances([10.0, 0.0, 100.0, 0.0, 2_000.0, 0.0]);
+        let (mint, amount, direction) = analyze(pre, post).await.unwrap();
+        assert_eq!((mint, amount, direction), (large, 1_500.0, SwapDirection::Sell));
+    }
+
+    /// Balances pre/post d'un achat de 1 000 tokens contre 1 SOL, pool (owner Raydium V4) aux réserves `pool_token` / 500 SOL
+    fn buy_balances(token: &Pubkey, user: &str, pool_token: f64) -> (Vec<UiTransactionTokenBalance>, Vec<UiTransactionTokenBalance>) {
+        let pool = RAYDIUM_V4_PROGRAM;
+        (
+            vec![
+                token_balance(1, token, user, 0.0, 6),
+                token_balance(2, &wsol(), user, 10.0, 9),
+                token_balance(3, token, pool, pool_token, 6),
+                token_balance(4, &wsol(), pool, 500.0, 9),
+            ],
+            vec![
+                token_balance(1, token, user, 1_000.0, 6),
+                token_balance(2, &wsol(), user, 9.0, 9),
+                token_balance(3, token, pool, (pool_token - 1_000.0).max(0.0), 6),
+                token_balance(4, &wsol(), pool, 501.0, 9),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn each_analysis_path_yields_its_error_kind() {
+        let rpc = FakeRpc::new();
+        let engine = engine(&rpc);
+        let (user, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (user_str, pool_str) = (user.to_string(), RAYDIUM_V4_PROGRAM.to_string());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+
+        let analyze = |signer: Pubkey, pre: Vec<UiTransactionTokenBalance>, post: Vec<UiTransactionTokenBalance>| {
+            let signature = Signature::new_unique().to_string();
+            rpc.add_transaction(&signature, parsed_transaction_json(&signature, &[(signer, true)], &pre, &post));
+            let engine = &engine;
+            async move {
+                let deadline = Deadline::after(Duration::from_secs(5));
+                let error = engine.calculate_tokens_received_and_mcap_impact_within(&signature, 0.0, &deadline).await.err().expect("analyse en erreur");
+                metrics::error_kind(&error)
+            }
+        };
+        let buy = |pool_token: f64| buy_balances(&token, &user_str, pool_token);
+
+        let (pre, post) = buy(1_000_000.0);
+        assert_eq!(analyze(engine.config.keypair.pubkey(), pre.clone(), post.clone()).await, "own_transaction");
+        // Prix SOL pas encore connu : impossible d'identifier le mint payé par la cible
+        assert_eq!(analyze(user, pre, post).await, "sol_price_unavailable");
+        *engine.sol_price.write().await = Some(150.0);
+
+        let (pre, post) = buy(0.0);
+        assert_eq!(analyze(user, pre, post).await, "pool_parse_failed");
+
+        let (pre, post) = buy(1_000_000.0);
+        let without_pool = |balances: Vec<UiTransactionTokenBalance>| balances.into_iter().take(2).collect::<Vec<_>>();
+        assert_eq!(analyze(user, without_pool(pre), without_pool(post)).await, "no_dex_pool");
+
+        // Vente : tokens rendus contre du SOL
+        let (pre, post) = buy(1_000_000.0);
+        assert_eq!(analyze(user, post, pre).await, "sell_swap");
+
+        // Seul le solde SOL de l'utilisateur bouge
+        let sol_only = |amount: f64| vec![token_balance(2, &wsol(), &user_str, amount, 9)];
+        assert_eq!(analyze(user, sol_only(10.0), sol_only(9.0)).await, "no_non_system_token");
+
+        // Aller-retour : 1 000 tokens achetés puis revendus dans la même transaction
+        let other_pool = Pubkey::new_unique().to_string();
+        let round_trip = |first_pool: f64, second_pool: f64| {
+            vec![
+                token_balance(1, &token, &user_str, 50.0, 6),
+                token_balance(3, &token, &pool_str, first_pool, 6),
+                token_balance(5, &token, &other_pool, second_pool, 6),
+            ]
+        };
+        let (pre, post) = (round_trip(10_000.0, 10_000.0), round_trip(9_000.0, 11_000.0));
+        assert_eq!(analyze(user, pre, post).await, "round_trip");
+
+        // Budget épuisé avant même getTransaction
+        let error = engine
+            .calculate_tokens_received_and_mcap_impact_within(&Signature::new_unique().to_string(), 0.0, &Deadline::after(Duration::ZERO))
+            .await
+            .err()
+            .expect("analyse en erreur");
+        assert_eq!(metrics::error_kind(&error), "rpc_timeout");
+    }
+
+    #[tokio::test]
+    async fn replay_analyzes_the_fixture_signatures_in_file_order() {
+        let path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay_signatures.txt"));
+        let fixture = std::fs::read_to_string(path).unwrap();
+        let sigs: Vec<&str> = fixture.lines().filter(|line| !line.is_empty() && !line.starts_with('#')).collect();
+        let [buy_sig, sell_sig, round_trip_sig, _unknown] = sigs[..] else { panic!("fixture: 4 signatures attendues") };
+
+        let rpc = FakeRpc::new();
+        let (user, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let user_str = user.to_string();
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        let (pre, post) = buy_balances(&token, &user_str, 1_000_000.0);
+        rpc.add_transaction(buy_sig, parsed_transaction_json(buy_sig, &[(user, true)], &pre, &post));
+        rpc.add_transaction(sell_sig, parsed_transaction_json(sell_sig, &[(user, true)], &post, &pre));
+        let other_pool = Pubkey::new_unique().to_string();
+        let round_trip = |first_pool: f64, second_pool: f64| {
+            vec![
+                token_balance(1, &token, &user_str, 50.0, 6),
+                token_balance(3, &token, RAYDIUM_V4_PROGRAM, first_pool, 6),
+                token_balance(5, &token, &other_pool, second_pool, 6),
+            ]
+        };
+        let (pre, post) = (round_trip(10_000.0, 10_000.0), round_trip(9_000.0, 11_000.0));
+        rpc.add_transaction(round_trip_sig, parsed_transaction_json(round_trip_sig, &[(user, true)], &pre, &post));
+
+        let engine = engine(&rpc);
+        *engine.sol_price.write().await = Some(150.0);
+        let results = engine.analyze_signatures_from_file(path).await.unwrap();
+
+        // Vente et signature inconnue du RPC : en erreur, omises
+        let signatures: Vec<&str> = results.iter().map(|result| result.signature.as_str()).collect();
+        assert_eq!(signatures, [buy_sig, round_trip_sig]);
+        let buy = &results[0];
+        assert_eq!(buy.classification, TransactionClassification::Swap);
+        assert_eq!(buy.tokens_received, 1_000.0);
+        assert!((buy.invested_amount - 150.0).abs() < 1e-6, "investi {}", buy.invested_amount);
+        assert!(buy.mcap_impact > 0.0);
+        assert_eq!(results[1].classification, TransactionClassification::RoundTrip);
+    }
+
+    /// Valeur de l'échantillon `sample` (labels compris) dans le texte servi sur `/metrics`
+    fn scraped(sample: &str) -> f64 {
+        let text = metrics::render().unwrap();
+        text.lines()
+            .find_map(|line| line.strip_prefix(sample)?.strip_prefix(' ')?.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    #[tokio::test]
+    async fn websocket_counters_move_in_the_scraped_metrics() {
+        let engine = engine(&FakeRpc::new());
+        let (logs_sender, logs_receiver) = crossbeam_channel::unbounded();
+        let (tx_sender, _tx_receiver) = mpsc::unbounded_channel();
+        let received = scraped("sandwich_bot_transactions_received_total");
+        let matched = scraped("sandwich_bot_dex_transactions_matched_total");
+
+        // Une transaction DEX reçue deux fois, puis une transaction sans programme DEX
+        let notification = |signature: &str, program: &str| Response {
+            context: solana_client::rpc_response::RpcResponseContext { slot: 1, api_version: None },
+            value: RpcLogsResponse {
+                signature: signature.to_string(),
+                err: None,
+                logs: vec![format!("Program {} invoke [1]", program)],
+            },
+        };
+        let system_program = solana_sdk::system_program::id().to_string();
+        logs_sender.send(notification("scrape-dex", RAYDIUM_V4_PROGRAM)).unwrap();
+        logs_sender.send(notification("scrape-dex", RAYDIUM_V4_PROGRAM)).unwrap();
+        logs_sender.send(notification("scrape-transfer", &system_program)).unwrap();
+        drop(logs_sender);
+        MonitoringEngine::process_websocket_logs(
+            logs_receiver,
+            tx_sender,
+            Arc::clone(&engine.config),
+            Arc::clone(&engine.async_rpc),
+            Arc::clone(&engine.seen_signatures),
+        )
+        .await;
+
+        // Les autres tests du module peuvent aussi incrémenter ces compteurs globaux
+        assert!(scraped("sandwich_bot_transactions_received_total") >= received + 3.0);
+        assert!(scraped("sandwich_bot_dex_transactions_matched_total") >= matched + 1.0);
+
+        let error = anyhow::Error::from(AnalysisError::SolPriceUnavailable);
+        let errors = scraped("sandwich_bot_analysis_errors_total{kind=\"sol_price_unavailable\"}");
+        metrics::ANALYSIS_ERRORS.with_label_values(&[metrics::error_kind(&error)]).inc();
+        assert_eq!(scraped("sandwich_bot_analysis_errors_total{kind=\"sol_price_unavailable\"}"), errors + 1.0);
+    }
+
+    /// Pool Raydium V4 WSOL/USDC `config.sol_usdc_pool` servie par `rpc` : `sol` SOL face à `usdc_amount` USDC.
+    /// Compte synthétique écrit d'après le layout publié (`raydium_v4_account_data`), pas un dump capturé
+    fn add_sol_usdc_pool(rpc: &FakeRpc, config: &BotConfig, sol: u64, usdc_amount: u64) {
+        let (sol_vault, usdc_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = config.sol_usdc_pool;
+        rpc.add_account(usdc(), mint_account(6, 0));
+        rpc.add_account(sol_vault, token_account(&wsol(), &pool, sol * 1_000_000_000));
+        rpc.add_account(usdc_vault, token_account(&usdc(), &pool, usdc_amount * 1_000_000));
+        let data = raydium_v4_account_data(&wsol(), &sol_vault, &usdc(), &usdc_vault, (9, 6));
+        rpc.add_account(pool, program_account(&Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap(), data));
+    }
+
+    #[tokio::test]
+    async fn sol_price_sources_are_tried_in_order_until_a_plausible_price() {
+        let mut config = test_config();
+        config.sol_price_sources = vec![SolPriceSource::Pyth, SolPriceSource::OnChainPool];
+        let now = chrono::Utc::now().timestamp();
+
+        // Pyth frais et plausible : la pool n'est pas lue
+        let rpc = FakeRpc::new();
+        rpc.add_account(config.pyth_sol_usd_account, pyth_price_update_account(15_012_345_678, -8, now));
+        add_sol_usdc_pool(&rpc, &config, 1_000, 160_000);
+        let price = MonitoringEngine::fetch_sol_price(&rpc.client(), &config).await.unwrap();
+        assert!((price - 150.12345678).abs() < 1e-9, "prix {}", price);
+        assert_eq!(rpc.count("getMultipleAccounts"), 0);
+
+        // Pyth périmé, puis Pyth hors bornes : repli sur la pool (160 000 USDC / 1 000 SOL)
+        for (pyth_price, publish_time) in [(15_000_000_000, now - 3_600), (500_000_000_000, now)] {
+            let rpc = FakeRpc::new();
+            rpc.add_account(config.pyth_sol_usd_account, pyth_price_update_account(pyth_price, -8, publish_time));
+            add_sol_usdc_pool(&rpc, &config, 1_000, 160_000);
+            let price = MonitoringEngine::fetch_sol_price(&rpc.client(), &config).await.unwrap();
+            assert!((price - 160.0).abs() < 1e-9, "prix {}", price);
+        }
+
+        // Aucune source : erreur listant chacune
+        let error = MonitoringEngine::fetch_sol_price(&FakeRpc::new().client(), &config).await.unwrap_err().to_string();
+        assert!(error.contains("Pyth") && error.contains("OnChainPool"), "{}", error);
+    }
+
+    #[test]
+    fn pyth_price_update_decodes_both_verification_levels() {
+        let full = pyth_price_update_account(15_012_345_678, -8, 1_700_000_000);
+        let update = PythPriceUpdate::from_account_data(&full.data).unwrap();
+        assert_eq!((update.price, update.exponent, update.publish_time), (15_012_345_678, -8, 1_700_000_000));
+        assert!((update.price_f64() - 150.12345678).abs() < 1e-9);
+
+        // Partial { num_signatures } : un octet de plus avant le price message
+        let mut partial = full.data[..40].to_vec();
+        partial.extend_from_slice(&[0, 5]);
+        partial.extend_from_slice(&full.data[41..]);
+        let update = PythPriceUpdate::from_account_data(&partial).unwrap();
+        assert_eq!((update.price, update.exponent, update.publish_time), (15_012_345_678, -8, 1_700_000_000));
+
+        assert!(PythPriceUpdate::from_account_data(&full.data[..80]).is_err());
+        let mut unknown = full.data.clone();
+        unknown[40] = 7;
+        assert!(PythPriceUpdate::from_account_data(&unknown).is_err());
+    }
+
+    #[tokio::test]
+    async fn sol_price_from_a_synthetic_wsol_usdc_pool_account() {
+        let config = test_config();
+        // Compte synthétique à l'adresse de la pool 58oQChx4…, au layout Raydium V4 publié :
+        // 20 000 SOL (9 décimales) face à 3 000 000 USDC (6 décimales)
+        let rpc = FakeRpc::new();
+        add_sol_usdc_pool(&rpc, &config, 20_000, 3_000_000);
+        let price = MonitoringEngine::fetch_sol_price_from_pool(&rpc.client(), &config).await.unwrap();
+        assert!((price - 150.0).abs() < 1e-9, "prix {}", price);
+        assert!((config.sol_price_min..=config.sol_price_max).contains(&price));
+
+        // Une autre paire à cette adresse est refusée plutôt que lue comme un prix SOL
+        let rpc = FakeRpc::new();
+        let (token, vault_a, vault_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 0));
+        rpc.add_account(vault_a, token_account(&token, &config.sol_usdc_pool, 1_000_000));
+        rpc.add_account(vault_b, token_account(&usdc(), &config.sol_usdc_pool, 1_000_000));
+        let data = raydium_v4_account_data(&token, &vault_a, &usdc(), &vault_b, (6, 6));
+        rpc.add_account(config.sol_usdc_pool, program_account(&Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap(), data));
+        assert!(MonitoringEngine::fetch_sol_price_from_pool(&rpc.client(), &config).await.is_err());
+    }
+
+    #[test]
+    fn account_keys_include_lookup_table_addresses_once() {
+        let (user, token, table) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let program = Pubkey::from_str(RAYDIUM_V4_PROGRAM).unwrap();
+        let ata = spl_associated_token_account::get_associated_token_address(&user, &token);
+        let (pre, post) = (
+            vec![token_balance(2, &token, "", 0.0, 6)],
+            vec![token_balance(2, &token, "", 1_000.0, 6)],
+        );
+
+        // json brut v0 : l'ATA de la cible n'est pas dans les clés statiques, seulement dans meta.loaded_addresses
+        let mut raw = parsed_transaction_json("v0", &[], &pre, &post);
+        raw["transaction"]["message"] = serde_json::json!({
+            "header": { "numRequiredSignatures": 1, "numReadonlySignedAccounts": 0, "numReadonlyUnsignedAccounts": 1 },
+            "accountKeys": [user.to_string(), program.to_string()],
+            "recentBlockhash": Pubkey::default().to_string(),
+            "instructions": [],
+            "addressTableLookups": [{ "accountKey": table.to_string(), "writableIndexes": [0], "readonlyIndexes": [] }],
+        });
+        raw["meta"]["loadedAddresses"] = serde_json::json!({ "writable": [ata.to_string()], "readonly": [] });
+        raw["version"] = serde_json::json!(0);
+        let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(raw.clone()).unwrap();
+        let keys = MonitoringEngine::all_account_keys(&tx);
+        assert_eq!(keys, vec![user.to_string(), program.to_string(), ata.to_string()]);
+        assert_eq!(
+            MonitoringEngine::tokens_received_from_signer_atas(&pre, &post, &user.to_string(), &keys),
+            Some((token, 1_000.0))
+        );
+
+        // Lookup table non résolue : clés statiques seules, l'index 2 reste inconnu
+        raw["meta"].as_object_mut().unwrap().remove("loadedAddresses");
+        let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(raw).unwrap();
+        let keys = MonitoringEngine::all_account_keys(&tx);
+        assert_eq!(keys, vec![user.to_string(), program.to_string()]);
+        assert_eq!(MonitoringEngine::tokens_received_from_signer_atas(&pre, &post, &user.to_string(), &keys), None);
+
+        // jsonParsed : account_keys contient déjà les adresses chargées (source "lookupTable"), sans doublon
+        let mut parsed = parsed_transaction_json("v0", &[(user, true), (program, false)], &pre, &post);
+        parsed["transaction"]["message"]["accountKeys"].as_array_mut().unwrap().push(serde_json::json!({
+            "pubkey": ata.to_string(), "writable": true, "signer": false, "source": "lookupTable",
+        }));
+        parsed["meta"]["loadedAddresses"] = serde_json::json!({ "writable": [ata.to_string()], "readonly": [] });
+        parsed["version"] = serde_json::json!(0);
+        let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(parsed).unwrap();
+        assert_eq!(MonitoringEngine::all_account_keys(&tx), vec![user.to_string(), program.to_string(), ata.to_string()]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sol_price_updater_retries_quickly_until_a_real_price_arrives() {
+        let mut config = test_config();
+        config.sol_price_sources = vec![SolPriceSource::OnChainPool];
+        config.sol_price_cache_path = None;
+        let rpc = FakeRpc::new();
+        let engine = MonitoringEngine::new(
+            Arc::new(config),
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            rpc.client(),
+            Arc::new(RwLock::new(AHashMap::new())),
+            AHashMap::new(),
+            Arc::new(RwLock::new(AHashMap::new())),
+        );
+
+        // Pool absente au démarrage : prix de repli
+        engine.start_sol_price_updater().await;
+        assert_eq!(*engine.sol_price.read().await, Some(engine.config.sol_price_fallback));
+
+        // Nouveaux essais à 5s puis 15s (backoff doublé), bien avant les 10 minutes
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        add_sol_usdc_pool(&rpc, &engine.config, 20_000, 3_000_000);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(*engine.sol_price.read().await, Some(150.0));
+
+        // Prix réel obtenu : retour au rythme de 10 minutes
+        let fetches = rpc.count("getMultipleAccounts");
+        tokio::time::sleep(Duration::from_secs(300)).await;
+        assert_eq!(rpc.count("getMultipleAccounts"), fetches);
+        tokio::time::sleep(Duration::from_secs(301)).await;
+        assert!(rpc.count("getMultipleAccounts") > fetches);
+    }
+}
@@ -1,4 +1,6 @@
+use lazy_static::lazy_static;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 // ============================================================================
@@ -11,6 +13,7 @@ pub const KNOWN_DEX_PROGRAMS: &[(&str, &str)] = &[
     ("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", "Raydium V4"),
     ("RVKd61ztZW9GUwhRbbLoYVRE5Xf1B2tVscKqwZqXgEr", "Raydium V3"),
     ("HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8", "Raydium V2"),
+    ("CAMMCzo5YL8w4VFF8KVHrK22GGUQpFuLUUamH4uV8K9", "Raydium CLMM"),
     
     // Orca
     ("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc", "Orca Whirlpool"),
@@ -19,7 +22,6 @@ pub const KNOWN_DEX_PROGRAMS: &[(&str, &str)] = &[
     
     // Meteora
     ("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo", "Meteora DLMM"),
-    ("CAMMCzo5YL8w4VFF8KVHrK22GGUQpFuLUUamH4uV8K9", "Meteora PCL"),
     ("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB", "Meteora V1"),
     
     // Jupiter
@@ -30,6 +32,10 @@ pub const KNOWN_DEX_PROGRAMS: &[(&str, &str)] = &[
     // Serum
     ("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin", "Serum DEX V3"),
     ("EUqojwWA2rd19FZrzeBncJsm38Jm1hEhE3zsmX3bRc2o", "Serum DEX V2"),
+
+    // OpenBook
+    ("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb", "OpenBook V2"),
+    ("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX", "OpenBook V1"),
     
     // Aldrin
     ("CURVGoZn8zycx6FXwwevgBTB2gVvdbGTEpvMJDbgs2t4", "Aldrin V2"),
@@ -59,7 +65,7 @@ pub const KNOWN_DEX_PROGRAMS: &[(&str, &str)] = &[
     ("PhoeNiLZ3D1nw8vKqJm8vKqJm8vKqJm8vKqJm8vKqJm", "Phoenix"),
     ("PhoeNiLZ3D1nw8vKqJm8vKqJm8vKqJm8vKqJm8vKqJm", "Phoenix V2"),
     
-    // Pump.fun (NON SUPPORTÉ)
+    // Pump.fun (bonding curve, voir DexType::PumpFun)
     ("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P", "Pump.fun"),
     ("BSfD6SHZigAfDWSjzD5Q41jw8LmKwtmjskPH9XW1mrRW", "Pump.fun Bonding Curve"),
 ];
@@ -94,28 +100,37 @@ pub const KNOWN_POOL_ACCOUNTS: &[(&str, &str)] = &[
     ("EUqojwWA2rd19FZrzeBncJsm38Jm1hEhE3zsmX3bRc2o", "Serum Market #2"),
 ];
 
+// ============================================================================
+// INDEX DE RECHERCHE
+// ============================================================================
+
+lazy_static! {
+    // Appelés pour chaque owner de chaque transaction : index construits une seule fois
+    static ref DEX_PROGRAMS_BY_ADDRESS: HashMap<&'static str, &'static str> = index_by_address(KNOWN_DEX_PROGRAMS);
+    static ref POOL_ACCOUNTS_BY_ADDRESS: HashMap<&'static str, &'static str> = index_by_address(KNOWN_POOL_ACCOUNTS);
+}
+
+/// Adresse -> nom ; pour une adresse listée plusieurs fois, la première entrée l'emporte (comme le parcours linéaire)
+fn index_by_address(entries: &[(&'static str, &'static str)]) -> HashMap<&'static str, &'static str> {
+    let mut index = HashMap::with_capacity(entries.len());
+    for (address, name) in entries {
+        index.entry(*address).or_insert(*name);
+    }
+    index
+}
+
 // ============================================================================
 // FONCTIONS UTILITAIRES
 // ============================================================================
 
 /// Vérifie si une adresse est un programme DEX connu
 pub fn is_known_dex_program(address: &str) -> Option<&str> {
-    for (program_id, name) in KNOWN_DEX_PROGRAMS {
-        if address == *program_id {
-            return Some(name);
-        }
-    }
-    None
+    DEX_PROGRAMS_BY_ADDRESS.get(address).copied()
 }
 
 /// Vérifie si une adresse est un compte de pool connu
 pub fn is_known_pool_account(address: &str) -> Option<&str> {
-    for (account_id, name) in KNOWN_POOL_ACCOUNTS {
-        if address == *account_id {
-            return Some(name);
-        }
-    }
-    None
+    POOL_ACCOUNTS_BY_ADDRESS.get(address).copied()
 }
 
 /// Obtient toutes les adresses de programmes DEX connus
@@ -146,15 +161,7 @@ pub fn parse_pubkey(address: &str) -> Result<Pubkey, String> {
 
 /// Obtient les informations complètes sur une adresse DEX
 pub fn get_dex_info(address: &str) -> Option<(&'static str, &'static str)> {
-    for (program_id, name) in KNOWN_DEX_PROGRAMS {
-        if address == *program_id {
-            return Some((*program_id, *name));
-        }
-    }
-    for (account_id, name) in KNOWN_POOL_ACCOUNTS {
-        if address == *account_id {
-            return Some((*account_id, *name));
-        }
-    }
-    None
+    DEX_PROGRAMS_BY_ADDRESS.get_key_value(address)
+        .or_else(|| POOL_ACCOUNTS_BY_ADDRESS.get_key_value(address))
+        .map(|(address, name)| (*address, *name))
 }
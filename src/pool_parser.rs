@@ -1,16 +1,21 @@
 use crate::types::{
-    DexType, PoolInfo, RaydiumAmmInfo, OrcaWhirlpoolInfo, 
-    MeteoraDLMMInfo, LifinityPoolInfo, PhoenixMarketInfo, SerumMarketInfo,
-    WSOL_MINT, USDC_MINT, USDT_MINT
+    DexType, PoolInfo, PoolSkipReason, RaydiumAmmInfo, RaydiumClmmInfo, OrcaWhirlpoolInfo, 
+    MeteoraDLMMInfo, MeteoraBinArray, DlmmBin, LifinityPoolInfo, PhoenixMarketInfo, SerumMarketInfo, OpenBookV2Market, OpenBookV2BookSide, OrderBookSummary, PumpFunBondingCurve,
+    WSOL_MINT, USDC_MINT, USDT_MINT, PUMPFUN_TOKEN_DECIMALS, PUMPFUN_FEE_BPS, RAYDIUM_CLMM_DEFAULT_FEE_BPS
 };
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{account::Account, pubkey::Pubkey};
 use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
-use spl_token::state::Account as TokenAccount;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_account_decoder::UiAccountData;
+use spl_token::state::{Account as TokenAccount, Mint};
 use solana_sdk::program_pack::Pack;
 use borsh::BorshDeserialize;
 use anyhow::{Result, anyhow};
 use std::str::FromStr;
 use std::sync::Arc;
+use ahash::AHashMap;
+use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
 
 // ============================================================================
 // POOL PARSER - GESTION DE TOUS LES TYPES DE POOLS
@@ -19,6 +24,30 @@ use std::sync::Arc;
 pub struct PoolParser {
     pub async_rpc: Arc<AsyncRpcClient>,
     pub sol_price_usd: f64,
+    // Décimales par mint (immuables on-chain, donc jamais invalidées)
+    pub decimals_cache: Arc<RwLock<AHashMap<Pubkey, u8>>>,
+    // Décimales supposées après un fetch échoué (mint -> date), pour ne pas re-échouer en boucle
+    pub decimals_fallback_cache: Arc<RwLock<AHashMap<Pubkey, Instant>>>,
+    // Fetch des décimales échoué : supposer `default_decimals` au lieu de faire échouer le parsing
+    pub assume_default_decimals: bool,
+    pub default_decimals: u8,
+    pub decimals_fallback_ttl: Duration,
+    // Meteora DLMM : récupérer les bin arrays autour du bin actif (RPC supplémentaire)
+    pub fetch_dlmm_bin_arrays: bool,
+    pub dlmm_bin_array_radius: i64,
+    // Orca : vérifier que les vaults décodés détiennent bien les mints de la whirlpool (RPC supplémentaire)
+    pub verify_orca_vaults: bool,
+    // Récupérer vaults et mints d'un pool en un seul getMultipleAccounts (sinon un appel par compte)
+    pub batch_account_fetches: bool,
+}
+
+/// Comptes d'un pool lus ensemble : réserves des vaults et supply des mints (None si non récupérée)
+#[derive(Debug, Clone, Copy)]
+pub struct PoolAccounts {
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub supply_a: Option<u64>,
+    pub supply_b: Option<u64>,
 }
 
 impl PoolParser {
@@ -26,6 +55,15 @@ impl PoolParser {
         Self {
             async_rpc,
             sol_price_usd: 150.0, // Prix par défaut, sera mis à jour
+            decimals_cache: Arc::new(RwLock::new(AHashMap::new())),
+            decimals_fallback_cache: Arc::new(RwLock::new(AHashMap::new())),
+            assume_default_decimals: true,
+            default_decimals: 9,
+            decimals_fallback_ttl: Duration::from_secs(30),
+            fetch_dlmm_bin_arrays: false,
+            dlmm_bin_array_radius: 1,
+            verify_orca_vaults: false,
+            batch_account_fetches: true,
         }
     }
 
@@ -36,18 +74,30 @@ impl PoolParser {
     /// Parse un pool en fonction du type de DEX
     pub async fn parse_pool(&self, pool_id: &Pubkey, dex_type: DexType, program_id: Pubkey) -> Result<PoolInfo> {
         let account = self.async_rpc.get_account(pool_id).await?;
-        
-        match dex_type {
-            DexType::RaydiumV4 => self.parse_raydium_v4(&account.data, *pool_id, program_id).await,
-            DexType::OrcaWhirlpool => self.parse_orca_whirlpool(&account.data, *pool_id, program_id).await,
-            DexType::MeteoraDLMM => self.parse_meteora_dlmm(&account.data, *pool_id, program_id).await,
-            DexType::Lifinity => self.parse_lifinity(&account.data, *pool_id, program_id).await,
-            DexType::Phoenix => self.parse_phoenix(&account.data, *pool_id, program_id).await,
-            DexType::Serum => self.parse_serum(&account.data, *pool_id, program_id).await,
+        check_account_owner(&account, pool_id, &dex_type, &program_id)?;
+        self.parse_pool_account(&account.data, pool_id, dex_type, program_id).await
+    }
+
+    /// Parse les données d'un compte pool déjà récupéré
+    pub async fn parse_pool_account(&self, data: &[u8], pool_id: &Pubkey, dex_type: DexType, program_id: Pubkey) -> Result<PoolInfo> {
+        let mut pool = match dex_type {
+            DexType::RaydiumV4 => self.parse_raydium_v4(data, *pool_id, program_id).await,
+            DexType::RaydiumCLMM => self.parse_raydium_clmm(data, *pool_id, program_id).await,
+            DexType::OrcaWhirlpool => self.parse_orca_whirlpool(data, *pool_id, program_id).await,
+            DexType::MeteoraDLMM => self.parse_meteora_dlmm(data, *pool_id, program_id).await,
+            DexType::Lifinity => self.parse_lifinity(data, *pool_id, program_id).await,
+            DexType::Phoenix => self.parse_phoenix(data, *pool_id, program_id).await,
+            DexType::Serum => self.parse_serum(data, *pool_id, program_id).await,
+            DexType::OpenBookV2 => self.parse_openbook_v2(data, *pool_id, program_id).await,
+            DexType::PumpFun => self.parse_pumpfun(data, *pool_id, program_id).await,
             DexType::Jupiter => Err(anyhow!("Jupiter est un agrégateur, pas un pool direct")),
             DexType::Unsupported => Err(anyhow!("Type de DEX non supporté")),
             DexType::Unknown => Err(anyhow!("Type de DEX inconnu")),
-        }
+        }?;
+
+        pool.decimals_assumed = self.is_decimals_assumed(&pool.token_a_mint).await
+            || self.is_decimals_assumed(&pool.token_b_mint).await;
+        Ok(pool)
     }
 
     // ============================================================================
@@ -55,12 +105,12 @@ impl PoolParser {
     // ============================================================================
     
     async fn parse_raydium_v4(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let amm_info = RaydiumAmmInfo::try_from_slice(data)
+        let amm_info = RaydiumAmmInfo::try_from_slice(check_account_len(data, RaydiumAmmInfo::LEN, &pool_id, DexType::RaydiumV4)?)
             .map_err(|e| anyhow!("Erreur parsing Raydium V4: {}", e))?;
 
-        // Récupérer les réserves
-        let reserve_a = self.get_token_balance(&amm_info.base_vault).await?;
-        let reserve_b = self.get_token_balance(&amm_info.quote_vault).await?;
+        // Récupérer les réserves (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(&amm_info.base_vault, &amm_info.quote_vault, &amm_info.base_mint, &amm_info.quote_mint).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
         // Calculer les frais
         let fee_bps = if amm_info.swap_fee_denominator > 0 {
@@ -76,6 +126,7 @@ impl PoolParser {
                 &amm_info.quote_mint,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
         Ok(PoolInfo {
@@ -98,6 +149,11 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
         })
     }
 
@@ -106,22 +162,42 @@ impl PoolParser {
     // ============================================================================
     
     async fn parse_orca_whirlpool(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let whirlpool = OrcaWhirlpoolInfo::try_from_slice(data)
-            .map_err(|e| anyhow!("Erreur parsing Orca Whirlpool: {}", e))?;
+        let whirlpool = OrcaWhirlpoolInfo::from_account_data(data)?;
+        if self.verify_orca_vaults {
+            self.verify_whirlpool_vaults(&whirlpool, &pool_id).await?;
+        }
 
-        // Utiliser les montants des vaults directement depuis la structure
-        let reserve_a = whirlpool.token_vault_a_amount;
-        let reserve_b = whirlpool.token_vault_b_amount;
+        // Réserves lues dans les vaults (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(
+            &whirlpool.token_vault_a, &whirlpool.token_vault_b,
+            &whirlpool.token_mint_a, &whirlpool.token_mint_b,
+        ).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
         // Calculer la liquidité et le market cap
-        let (liquidity_usd, token_a_liquidity, token_b_liquidity, market_cap_usd, token_price_usd, total_supply) = 
+        let (liquidity_usd, token_a_liquidity, token_b_liquidity, mut market_cap_usd, mut token_price_usd, total_supply) = 
             self.calculate_pool_metrics(
                 &whirlpool.token_mint_a,
                 &whirlpool.token_mint_b,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
+        // Pool concentré : le prix vient de sqrt_price, pas du ratio des vaults
+        let decimals_a = self.get_mint_decimals(&whirlpool.token_mint_a).await?;
+        let decimals_b = self.get_mint_decimals(&whirlpool.token_mint_b).await?;
+        let price_a_in_b = sqrt_price_x64_to_price(whirlpool.sqrt_price, decimals_a, decimals_b);
+        if let Some((price, mcap)) = self.usd_price_and_mcap(
+            &whirlpool.token_mint_a,
+            &whirlpool.token_mint_b,
+            price_a_in_b,
+            total_supply,
+        ).await? {
+            token_price_usd = Some(price);
+            market_cap_usd = mcap;
+        }
+
         Ok(PoolInfo {
             dex_type: DexType::OrcaWhirlpool,
             program_id,
@@ -132,7 +208,7 @@ impl PoolParser {
             token_b_vault: whirlpool.token_vault_b,
             reserve_a,
             reserve_b,
-            fee_bps: whirlpool.fee_rate,
+            fee_bps: whirlpool.fee_bps(),
             tick_spacing: Some(whirlpool.tick_spacing as i32),
             tick_current: Some(whirlpool.tick_current_index),
             bin_step: None,
@@ -142,6 +218,115 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
+        })
+    }
+
+    /// Un décalage de layout (upgrade du programme) ferait lire des clés au mauvais offset : les vaults
+    /// décodés doivent être des token accounts des mints décodés, sinon le parsing est refusé
+    async fn verify_whirlpool_vaults(&self, whirlpool: &OrcaWhirlpoolInfo, pool_id: &Pubkey) -> Result<()> {
+        let vaults = self.fetch_accounts_batch(&[whirlpool.token_vault_a, whirlpool.token_vault_b]).await?;
+        for (vault, (key, mint)) in vaults.iter().zip([
+            (whirlpool.token_vault_a, whirlpool.token_mint_a),
+            (whirlpool.token_vault_b, whirlpool.token_mint_b),
+        ]) {
+            let vault_mint = vault.as_ref()
+                .and_then(|account| account.data.get(..32))
+                .and_then(|bytes| Pubkey::try_from(bytes).ok());
+            if vault_mint != Some(mint) {
+                log::error!(
+                    "🚨 Whirlpool {}: le vault {} ne détient pas le mint {} ({:?}) - layout Orca probablement modifié",
+                    pool_id, key, mint, vault_mint
+                );
+                return Err(anyhow!("Whirlpool {}: vault {} incohérent avec le mint {}", pool_id, key, mint));
+            }
+        }
+        Ok(())
+    }
+
+    // ============================================================================
+    // RAYDIUM CLMM PARSER
+    // ============================================================================
+
+    async fn parse_raydium_clmm(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
+        let clmm = RaydiumClmmInfo::from_account_data(data)?;
+
+        // Décimales stockées dans le pool : pas de fetch des mints pour elles
+        {
+            let mut cache = self.decimals_cache.write().await;
+            cache.insert(clmm.token_mint_0, clmm.mint_decimals_0);
+            cache.insert(clmm.token_mint_1, clmm.mint_decimals_1);
+        }
+
+        // Récupérer les réserves et l'AmmConfig du tier (frais), avec les mints, en un seul appel si activé
+        let (accounts, extra) = self.fetch_pool_accounts_with(
+            &clmm.token_vault_0,
+            &clmm.token_vault_1,
+            &clmm.token_mint_0,
+            &clmm.token_mint_1,
+            &[clmm.amm_config],
+        ).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
+
+        let fee_bps = match extra.first().and_then(|account| account.as_ref()) {
+            Some(account) => RaydiumClmmInfo::trade_fee_bps(&account.data).unwrap_or(RAYDIUM_CLMM_DEFAULT_FEE_BPS),
+            None => {
+                log::debug!("AmmConfig {} introuvable, frais par défaut", clmm.amm_config);
+                RAYDIUM_CLMM_DEFAULT_FEE_BPS
+            }
+        };
+
+        // Calculer la liquidité et le market cap
+        let (liquidity_usd, token_a_liquidity, token_b_liquidity, mut market_cap_usd, mut token_price_usd, total_supply) = 
+            self.calculate_pool_metrics(
+                &clmm.token_mint_0,
+                &clmm.token_mint_1,
+                reserve_a,
+                reserve_b,
+                Some(&accounts),
+            ).await?;
+
+        // Pool concentré : le prix vient de sqrt_price, pas du ratio des vaults
+        let price_a_in_b = sqrt_price_x64_to_price(clmm.sqrt_price_x64, clmm.mint_decimals_0, clmm.mint_decimals_1);
+        if let Some((price, mcap)) = self.usd_price_and_mcap(
+            &clmm.token_mint_0,
+            &clmm.token_mint_1,
+            price_a_in_b,
+            total_supply,
+        ).await? {
+            token_price_usd = Some(price);
+            market_cap_usd = mcap;
+        }
+
+        Ok(PoolInfo {
+            dex_type: DexType::RaydiumCLMM,
+            program_id,
+            pool_id,
+            token_a_mint: clmm.token_mint_0,
+            token_b_mint: clmm.token_mint_1,
+            token_a_vault: clmm.token_vault_0,
+            token_b_vault: clmm.token_vault_1,
+            reserve_a,
+            reserve_b,
+            fee_bps,
+            tick_spacing: Some(clmm.tick_spacing as i32),
+            tick_current: Some(clmm.tick_current),
+            bin_step: None,
+            liquidity_usd,
+            token_a_liquidity,
+            token_b_liquidity,
+            market_cap_usd,
+            token_price_usd,
+            total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
         })
     }
 
@@ -150,25 +335,54 @@ impl PoolParser {
     // ============================================================================
     
     async fn parse_meteora_dlmm(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let dlmm = MeteoraDLMMInfo::try_from_slice(data)
+        let dlmm = MeteoraDLMMInfo::from_account_data(data)
             .map_err(|e| anyhow!("Erreur parsing Meteora DLMM: {}", e))?;
 
-        // Récupérer les réserves
-        let reserve_a = self.get_token_balance(&dlmm.reserve_x).await?;
-        let reserve_b = self.get_token_balance(&dlmm.reserve_y).await?;
+        // Récupérer les réserves (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(&dlmm.reserve_x, &dlmm.reserve_y, &dlmm.mint_x, &dlmm.mint_y).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
-        // Calculer les frais totaux
-        let fee_bps = dlmm.protocol_fee_bps + dlmm.base_fee_bps;
+        // Frais de base (la part protocole est prélevée sur ces frais, pas ajoutée)
+        let fee_bps = dlmm.fee_parameters.base_fee_bps(dlmm.bin_step).round() as u16;
 
-        // Calculer la liquidité et le market cap
-        let (liquidity_usd, token_a_liquidity, token_b_liquidity, market_cap_usd, token_price_usd, total_supply) = 
+        // Liquidité réelle par bin, pour simuler le swap bin par bin
+        let dlmm_bins = if self.fetch_dlmm_bin_arrays {
+            match self.fetch_dlmm_bins(&program_id, &pool_id, dlmm.active_id).await {
+                Ok(bins) => Some(bins),
+                Err(e) => {
+                    log::warn!("⚠️ Bin arrays Meteora indisponibles pour {}: {}", pool_id, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Calculer la liquidité (estimation de TVL) et le market cap
+        let (liquidity_usd, token_a_liquidity, token_b_liquidity, mut market_cap_usd, mut token_price_usd, total_supply) = 
             self.calculate_pool_metrics(
                 &dlmm.mint_x,
                 &dlmm.mint_y,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
+        // AMM à bins : le prix spot est celui du bin actif, pas le ratio des réserves
+        let decimals_x = self.get_mint_decimals(&dlmm.mint_x).await?;
+        let decimals_y = self.get_mint_decimals(&dlmm.mint_y).await?;
+        let price_x_in_y = dlmm_bin_price(dlmm.active_id, dlmm.bin_step)
+            * 10f64.powi(decimals_x as i32 - decimals_y as i32);
+        if let Some((price, mcap)) = self.usd_price_and_mcap(
+            &dlmm.mint_x,
+            &dlmm.mint_y,
+            price_x_in_y,
+            total_supply,
+        ).await? {
+            token_price_usd = Some(price);
+            market_cap_usd = mcap;
+        }
+
         Ok(PoolInfo {
             dex_type: DexType::MeteoraDLMM,
             program_id,
@@ -189,20 +403,50 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins,
+            dlmm_fee: Some(dlmm.fee_parameters),
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
         })
     }
 
+    /// Récupère les bin arrays [index actif - rayon, index actif + rayon] d'une paire DLMM (bins triés par id)
+    async fn fetch_dlmm_bins(&self, program_id: &Pubkey, lb_pair: &Pubkey, active_id: i32) -> Result<Vec<DlmmBin>> {
+        let active_index = MeteoraBinArray::index_for_bin(active_id);
+        let radius = self.dlmm_bin_array_radius.max(0);
+        let addresses: Vec<Pubkey> = (active_index - radius..=active_index + radius)
+            .map(|index| MeteoraBinArray::address(program_id, lb_pair, index))
+            .collect();
+
+        let accounts = self.async_rpc.get_multiple_accounts(&addresses).await?;
+        let mut bins = Vec::new();
+        // Un bin array jamais initialisé (aucune liquidité dans la plage) n'existe pas
+        for account in accounts.into_iter().flatten() {
+            let bin_array = MeteoraBinArray::from_account_data(&account.data)?;
+            if bin_array.lb_pair != *lb_pair {
+                return Err(anyhow!("BinArray {} n'appartient pas à la paire {}", bin_array.index, lb_pair));
+            }
+            bins.extend(bin_array.bins);
+        }
+        if bins.is_empty() {
+            return Err(anyhow!("Aucun bin array initialisé autour du bin {}", active_id));
+        }
+        bins.sort_by_key(|bin| bin.id);
+        Ok(bins)
+    }
+
     // ============================================================================
     // LIFINITY PARSER
     // ============================================================================
     
     async fn parse_lifinity(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let lifinity = LifinityPoolInfo::try_from_slice(data)
+        let lifinity = LifinityPoolInfo::try_from_slice(check_account_len(data, LifinityPoolInfo::LEN, &pool_id, DexType::Lifinity)?)
             .map_err(|e| anyhow!("Erreur parsing Lifinity: {}", e))?;
 
-        // Récupérer les réserves
-        let reserve_a = self.get_token_balance(&lifinity.token_a_vault).await?;
-        let reserve_b = self.get_token_balance(&lifinity.token_b_vault).await?;
+        // Récupérer les réserves (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(&lifinity.token_a_vault, &lifinity.token_b_vault, &lifinity.token_a_mint, &lifinity.token_b_mint).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
         // Calculer la liquidité et le market cap
         let (liquidity_usd, token_a_liquidity, token_b_liquidity, market_cap_usd, token_price_usd, total_supply) = 
@@ -211,6 +455,7 @@ impl PoolParser {
                 &lifinity.token_b_mint,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
         Ok(PoolInfo {
@@ -233,6 +478,11 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
         })
     }
 
@@ -241,12 +491,12 @@ impl PoolParser {
     // ============================================================================
     
     async fn parse_phoenix(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let phoenix = PhoenixMarketInfo::try_from_slice(data)
+        let phoenix = PhoenixMarketInfo::try_from_slice(check_account_len(data, PhoenixMarketInfo::LEN, &pool_id, DexType::Phoenix)?)
             .map_err(|e| anyhow!("Erreur parsing Phoenix: {}", e))?;
 
-        // Récupérer les réserves
-        let reserve_a = self.get_token_balance(&phoenix.base_vault).await?;
-        let reserve_b = self.get_token_balance(&phoenix.quote_vault).await?;
+        // Récupérer les réserves (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(&phoenix.base_vault, &phoenix.quote_vault, &phoenix.base_mint, &phoenix.quote_mint).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
         // Calculer la liquidité et le market cap
         let (liquidity_usd, token_a_liquidity, token_b_liquidity, market_cap_usd, token_price_usd, total_supply) = 
@@ -255,6 +505,7 @@ impl PoolParser {
                 &phoenix.quote_mint,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
         Ok(PoolInfo {
@@ -277,6 +528,85 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
+        })
+    }
+
+    // ============================================================================
+    // OPENBOOK V2 PARSER
+    // ============================================================================
+
+    async fn parse_openbook_v2(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
+        let market = OpenBookV2Market::from_account_data(data)
+            .map_err(|e| anyhow!("Erreur parsing OpenBook v2: {}", e))?;
+
+        // Bids et asks en un seul appel
+        let sides = self.async_rpc.get_multiple_accounts(&[market.bids, market.asks]).await?;
+        let mut order_book = OrderBookSummary::default();
+        if let [Some(bids), Some(asks)] = sides.as_slice() {
+            let bids = OpenBookV2BookSide::fixed_orders(&bids.data)?;
+            let asks = OpenBookV2BookSide::fixed_orders(&asks.data)?;
+            (order_book.best_bid, order_book.bid_depth_base) = market.summarize_side(&bids, true);
+            (order_book.best_ask, order_book.ask_depth_base) = market.summarize_side(&asks, false);
+        } else {
+            return Err(anyhow!("Bids/asks introuvables pour le market OpenBook v2 {}", pool_id));
+        }
+
+        // Réserves = dépôts totaux du market (lus dans le compte, pas d'appel RPC)
+        let reserve_a = market.base_deposit_total;
+        let reserve_b = market.quote_deposit_total;
+
+        let (liquidity_usd, token_a_liquidity, token_b_liquidity, mut market_cap_usd, mut token_price_usd, total_supply) =
+            self.calculate_pool_metrics(
+                &market.base_mint,
+                &market.quote_mint,
+                reserve_a,
+                reserve_b,
+                None,
+            ).await?;
+
+        // Carnet d'ordres : le prix vient du milieu du carnet, pas du ratio des dépôts
+        if let Some(mid) = order_book.mid_price() {
+            if let Some((price, mcap)) = self.usd_price_and_mcap(
+                &market.base_mint,
+                &market.quote_mint,
+                mid,
+                total_supply,
+            ).await? {
+                token_price_usd = Some(price);
+                market_cap_usd = mcap;
+            }
+        }
+
+        Ok(PoolInfo {
+            dex_type: DexType::OpenBookV2,
+            program_id,
+            pool_id,
+            token_a_mint: market.base_mint,
+            token_b_mint: market.quote_mint,
+            token_a_vault: market.market_base_vault,
+            token_b_vault: market.market_quote_vault,
+            reserve_a,
+            reserve_b,
+            fee_bps: market.taker_fee_bps(),
+            tick_spacing: None,
+            tick_current: None,
+            bin_step: None,
+            liquidity_usd,
+            token_a_liquidity,
+            token_b_liquidity,
+            market_cap_usd,
+            token_price_usd,
+            total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: Some(order_book),
+            observed_quote_in: None,
         })
     }
 
@@ -285,12 +615,12 @@ impl PoolParser {
     // ============================================================================
     
     async fn parse_serum(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
-        let serum = SerumMarketInfo::try_from_slice(data)
+        let serum = SerumMarketInfo::try_from_slice(check_account_len(data, SerumMarketInfo::LEN, &pool_id, DexType::Serum)?)
             .map_err(|e| anyhow!("Erreur parsing Serum: {}", e))?;
 
-        // Récupérer les réserves
-        let reserve_a = self.get_token_balance(&serum.base_vault).await?;
-        let reserve_b = self.get_token_balance(&serum.quote_vault).await?;
+        // Récupérer les réserves (avec les mints, en un seul appel si activé)
+        let accounts = self.fetch_pool_accounts(&serum.base_vault, &serum.quote_vault, &serum.base_mint, &serum.quote_mint).await?;
+        let (reserve_a, reserve_b) = (accounts.reserve_a, accounts.reserve_b);
 
         // Calculer la liquidité et le market cap
         let (liquidity_usd, token_a_liquidity, token_b_liquidity, market_cap_usd, token_price_usd, total_supply) = 
@@ -299,6 +629,7 @@ impl PoolParser {
                 &serum.quote_mint,
                 reserve_a,
                 reserve_b,
+                Some(&accounts),
             ).await?;
 
         Ok(PoolInfo {
@@ -321,18 +652,238 @@ impl PoolParser {
             market_cap_usd,
             token_price_usd,
             total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
         })
     }
 
+    // ============================================================================
+    // PUMP.FUN PARSER
+    // ============================================================================
+    
+    async fn parse_pumpfun(&self, data: &[u8], pool_id: Pubkey, program_id: Pubkey) -> Result<PoolInfo> {
+        let curve = PumpFunBondingCurve::from_account_data(data)?;
+        if curve.complete {
+            return Err(anyhow!("Bonding curve Pump.fun terminée (token migré): {}", pool_id));
+        }
+
+        // Le compte bonding curve ne contient pas le mint : le retrouver via son token account
+        let token_mint = self.find_bonding_curve_mint(&pool_id).await?;
+        let wsol_mint = Pubkey::from_str(WSOL_MINT)?;
+
+        // Réserves virtuelles : ce sont elles qui définissent le prix de la courbe
+        let reserve_a = curve.virtual_token_reserves;
+        let reserve_b = curve.virtual_sol_reserves;
+        let token_a_liquidity = reserve_a as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS as i32);
+        let token_b_liquidity = reserve_b as f64 / 1e9;
+
+        let price_usd = curve.price_in_sol() * self.sol_price_usd;
+        let total_supply = Some(curve.token_total_supply);
+        let market_cap_usd = Some(
+            curve.token_total_supply as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS as i32) * price_usd
+        );
+
+        Ok(PoolInfo {
+            dex_type: DexType::PumpFun,
+            program_id,
+            pool_id,
+            token_a_mint: token_mint,
+            token_b_mint: wsol_mint,
+            token_a_vault: Pubkey::default(),
+            token_b_vault: pool_id, // Le SOL est détenu directement par la bonding curve
+            reserve_a,
+            reserve_b,
+            fee_bps: PUMPFUN_FEE_BPS,
+            tick_spacing: None,
+            tick_current: None,
+            bin_step: None,
+            // Seul le SOL réellement déposé compte comme liquidité
+            liquidity_usd: curve.real_sol_reserves as f64 / 1e9 * self.sol_price_usd * 2.0,
+            token_a_liquidity,
+            token_b_liquidity,
+            market_cap_usd,
+            token_price_usd: Some(price_usd),
+            total_supply,
+            dlmm_bins: None,
+            dlmm_fee: None,
+            decimals_assumed: false,
+            order_book: None,
+            observed_quote_in: None,
+        })
+    }
+
+    /// Retrouve le mint d'une bonding curve à partir du token account qu'elle possède
+    async fn find_bonding_curve_mint(&self, bonding_curve: &Pubkey) -> Result<Pubkey> {
+        let accounts = self.async_rpc
+            .get_token_accounts_by_owner(bonding_curve, TokenAccountsFilter::ProgramId(spl_token::id()))
+            .await?;
+
+        accounts.iter()
+            .find_map(|keyed| match &keyed.account.data {
+                UiAccountData::Json(parsed) => parsed.parsed["info"]["mint"].as_str()
+                    .and_then(|mint| Pubkey::from_str(mint).ok()),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("Aucun token account trouvé pour la bonding curve {}", bonding_curve))
+    }
+
     // ============================================================================
     // FONCTIONS UTILITAIRES
     // ============================================================================
 
-    /// Récupère la balance d'un token account
-    async fn get_token_balance(&self, token_account: &Pubkey) -> Result<u64> {
-        let account_data = self.async_rpc.get_account(token_account).await?;
-        let token_account = TokenAccount::unpack(&account_data.data)?;
-        Ok(token_account.amount)
+    /// Récupère plusieurs comptes en un minimum d'appels (getMultipleAccounts, 100 comptes max par appel),
+    /// dans l'ordre des clés demandées
+    pub async fn fetch_accounts_batch(&self, keys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let mut accounts = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(100) {
+            accounts.extend(self.async_rpc.get_multiple_accounts(chunk).await?);
+        }
+        Ok(accounts)
+    }
+
+    /// Balances de plusieurs token accounts en un minimum d'appels (getMultipleAccounts), dans l'ordre des clés
+    pub async fn get_token_balances_batch(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        let fetched = self.fetch_accounts_batch(accounts).await?;
+        accounts.iter().zip(&fetched)
+            .map(|(key, account)| token_account_amount(account.as_ref(), key))
+            .collect()
+    }
+
+    /// Réserves des deux vaults d'un pool, plus décimales (mises en cache) et supply des deux mints
+    async fn fetch_pool_accounts(
+        &self,
+        vault_a: &Pubkey,
+        vault_b: &Pubkey,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+    ) -> Result<PoolAccounts> {
+        let (accounts, _) = self.fetch_pool_accounts_with(vault_a, vault_b, mint_a, mint_b, &[]).await?;
+        Ok(accounts)
+    }
+
+    /// `fetch_pool_accounts` plus des comptes annexes du pool (ex. AmmConfig CLMM) lus dans le même appel,
+    /// retournés dans l'ordre de `extra` (None si absents)
+    async fn fetch_pool_accounts_with(
+        &self,
+        vault_a: &Pubkey,
+        vault_b: &Pubkey,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+        extra: &[Pubkey],
+    ) -> Result<(PoolAccounts, Vec<Option<Account>>)> {
+        if !self.batch_account_fetches {
+            let reserves = self.get_token_balances_batch(&[*vault_a, *vault_b]).await?;
+            let extra = if extra.is_empty() { Vec::new() } else { self.fetch_accounts_batch(extra).await? };
+            let accounts = PoolAccounts { reserve_a: reserves[0], reserve_b: reserves[1], supply_a: None, supply_b: None };
+            return Ok((accounts, extra));
+        }
+
+        let keys: Vec<Pubkey> = [*vault_a, *vault_b, *mint_a, *mint_b].into_iter().chain(extra.iter().copied()).collect();
+        let mut fetched = self.fetch_accounts_batch(&keys).await?;
+        let extra = fetched.split_off(4);
+
+        let reserve_a = token_account_amount(fetched[0].as_ref(), vault_a)?;
+        let reserve_b = token_account_amount(fetched[1].as_ref(), vault_b)?;
+
+        let mut supplies = [None, None];
+        for (slot, (index, mint)) in supplies.iter_mut().zip([(2, mint_a), (3, mint_b)]) {
+            let mint_state = fetched[index].as_ref()
+                .and_then(|a| a.data.get(..Mint::LEN))
+                .and_then(|data| Mint::unpack_from_slice(data).ok());
+            if let Some(mint_state) = mint_state {
+                self.decimals_cache.write().await.insert(*mint, mint_state.decimals);
+                *slot = Some(mint_state.supply);
+            }
+        }
+
+        Ok((PoolAccounts { reserve_a, reserve_b, supply_a: supplies[0], supply_b: supplies[1] }, extra))
+    }
+
+    /// Prix USD d'un token de quote connu (SOL, USDC, USDT)
+    fn quote_usd_price(&self, mint: &Pubkey) -> Option<f64> {
+        match mint.to_string().as_str() {
+            WSOL_MINT => Some(self.sol_price_usd),
+            USDC_MINT | USDT_MINT => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Convertit un prix spot (token A en token B, unités UI) en prix USD du token custom et
+    /// en market cap. Retourne None si aucun des deux côtés n'est un token de quote connu.
+    async fn usd_price_and_mcap(
+        &self,
+        token_a_mint: &Pubkey,
+        token_b_mint: &Pubkey,
+        price_a_in_b: f64,
+        total_supply: Option<u64>,
+    ) -> Result<Option<(f64, Option<f64>)>> {
+        let (custom_mint, price_usd) = match (self.quote_usd_price(token_a_mint), self.quote_usd_price(token_b_mint)) {
+            (None, Some(quote_b)) => (token_a_mint, price_a_in_b * quote_b),
+            (Some(quote_a), None) if price_a_in_b > 0.0 => (token_b_mint, quote_a / price_a_in_b),
+            _ => return Ok(None),
+        };
+
+        let market_cap_usd = match total_supply {
+            Some(supply) => {
+                let decimals = self.get_mint_decimals(custom_mint).await?;
+                Some(supply as f64 / 10f64.powi(decimals as i32) * price_usd)
+            }
+            None => None,
+        };
+
+        Ok(Some((price_usd, market_cap_usd)))
+    }
+
+    /// Décimales d'un mint déjà connues (mints système ou cache), sans appel RPC
+    pub async fn cached_decimals(&self, mint: &Pubkey) -> Option<u8> {
+        match mint.to_string().as_str() {
+            WSOL_MINT => Some(9),
+            USDC_MINT | USDT_MINT => Some(6),
+            _ => self.decimals_cache.read().await.get(mint).copied(),
+        }
+    }
+
+    /// Récupère les décimales d'un mint (avec cache)
+    pub async fn get_mint_decimals(&self, mint: &Pubkey) -> Result<u8> {
+        // Mints système connus ou déjà en cache : pas besoin d'appel RPC
+        if let Some(decimals) = self.cached_decimals(mint).await {
+            return Ok(decimals);
+        }
+
+        // Fallback récent : ne pas refaire un fetch qui vient d'échouer
+        if self.is_decimals_assumed(mint).await {
+            return Ok(self.default_decimals);
+        }
+
+        let fetched = async {
+            let account = self.async_rpc.get_account(mint).await?;
+            Mint::unpack(&account.data)
+                .map_err(|e| anyhow!("Erreur parsing mint {}: {}", mint, e))
+        }.await;
+
+        match fetched {
+            Ok(mint_state) => {
+                self.decimals_cache.write().await.insert(*mint, mint_state.decimals);
+                self.decimals_fallback_cache.write().await.remove(mint);
+                Ok(mint_state.decimals)
+            }
+            Err(e) if self.assume_default_decimals => {
+                log::warn!("⚠️ Décimales de {} indisponibles ({}), {} supposées", mint, e, self.default_decimals);
+                self.decimals_fallback_cache.write().await.insert(*mint, Instant::now());
+                Ok(self.default_decimals)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Vrai si les décimales du mint sont actuellement supposées (fallback encore valide)
+    async fn is_decimals_assumed(&self, mint: &Pubkey) -> bool {
+        self.decimals_fallback_cache.read().await
+            .get(mint)
+            .is_some_and(|at| at.elapsed() < self.decimals_fallback_ttl)
     }
 
     /// Récupère le total supply d'un token
@@ -348,6 +899,7 @@ impl PoolParser {
         token_b_mint: &Pubkey,
         reserve_a: u64,
         reserve_b: u64,
+        prefetched: Option<&PoolAccounts>,
     ) -> Result<(f64, f64, f64, Option<f64>, Option<f64>, Option<u64>)> {
         
         let wsol_mint = Pubkey::from_str(WSOL_MINT)?;
@@ -359,10 +911,12 @@ impl PoolParser {
             *token_b_mint == wsol_mint || *token_b_mint == usdc_mint,
         );
 
-        // Calculer la liquidité en USD
-        let mut liquidity_usd;
-        let token_a_liquidity = reserve_a as f64 / 1e9; // Assuming 9 decimals
-        let token_b_liquidity = reserve_b as f64 / 1e9;
+        // Calculer la liquidité en USD (réserves ramenées en unités UI avec les vraies décimales)
+        let liquidity_usd;
+        let decimals_a = self.get_mint_decimals(token_a_mint).await?;
+        let decimals_b = self.get_mint_decimals(token_b_mint).await?;
+        let token_a_liquidity = reserve_a as f64 / 10f64.powi(decimals_a as i32);
+        let token_b_liquidity = reserve_b as f64 / 10f64.powi(decimals_b as i32);
 
         // Calculer le prix et le market cap
         let mut token_price_usd = None;
@@ -379,16 +933,20 @@ impl PoolParser {
 
             liquidity_usd = stable_value * 2.0; // TVL totale = 2x la valeur stable
 
-            // Calculer le prix du token custom
+            // Calculer le prix du token custom (unités UI : les décimales des deux mints peuvent différer)
             if reserve_b > 0 {
-                let price = (reserve_a as f64 / reserve_b as f64) * 
+                let price = (token_a_liquidity / token_b_liquidity) * 
                     if *token_a_mint == wsol_mint { self.sol_price_usd } else { 1.0 };
                 token_price_usd = Some(price);
 
                 // Récupérer le supply total et calculer le mcap
-                if let Ok(supply) = self.get_token_supply(token_b_mint).await {
+                let supply = match prefetched.and_then(|accounts| accounts.supply_b) {
+                    Some(supply) => Ok(supply),
+                    None => self.get_token_supply(token_b_mint).await,
+                };
+                if let Ok(supply) = supply {
                     total_supply = Some(supply);
-                    market_cap_usd = Some((supply as f64 / 1e9) * price);
+                    market_cap_usd = Some((supply as f64 / 10f64.powi(decimals_b as i32)) * price);
                 }
             }
 
@@ -402,16 +960,20 @@ impl PoolParser {
 
             liquidity_usd = stable_value * 2.0; // TVL totale = 2x la valeur stable
 
-            // Calculer le prix du token custom
+            // Calculer le prix du token custom (unités UI : les décimales des deux mints peuvent différer)
             if reserve_a > 0 {
-                let price = (reserve_b as f64 / reserve_a as f64) * 
+                let price = (token_b_liquidity / token_a_liquidity) * 
                     if *token_b_mint == wsol_mint { self.sol_price_usd } else { 1.0 };
                 token_price_usd = Some(price);
 
                 // Récupérer le supply total et calculer le mcap
-                if let Ok(supply) = self.get_token_supply(token_a_mint).await {
+                let supply = match prefetched.and_then(|accounts| accounts.supply_a) {
+                    Some(supply) => Ok(supply),
+                    None => self.get_token_supply(token_a_mint).await,
+                };
+                if let Ok(supply) = supply {
                     total_supply = Some(supply);
-                    market_cap_usd = Some((supply as f64 / 1e9) * price);
+                    market_cap_usd = Some((supply as f64 / 10f64.powi(decimals_a as i32)) * price);
                 }
             }
 
@@ -433,7 +995,7 @@ impl PoolParser {
         } else {
             // Pool entre deux tokens customs - estimer la liquidité
             // Utiliser une heuristique basique
-            liquidity_usd = (reserve_a as f64 + reserve_b as f64) / 1e9 * 0.1; // Estimation très approximative
+            liquidity_usd = (token_a_liquidity + token_b_liquidity) * 0.1; // Estimation très approximative
         }
 
         Ok((
@@ -447,50 +1009,569 @@ impl PoolParser {
     }
 
     /// Vérifie si un pool est valide pour le sandwich attack
-    pub fn is_pool_valid_for_sandwich(&self, pool: &PoolInfo, min_liquidity: f64, max_liquidity: f64) -> bool {
+    pub fn is_pool_valid_for_sandwich(&self, pool: &PoolInfo, min_liquidity: f64, max_liquidity: f64, max_reserve_ratio: f64) -> bool {
+        self.check_pool_validity(pool, min_liquidity, max_liquidity, max_reserve_ratio).is_ok()
+    }
+
+    /// Comme `is_pool_valid_for_sandwich`, mais indique pourquoi le pool est écarté
+    pub fn check_pool_validity(
+        &self,
+        pool: &PoolInfo,
+        min_liquidity: f64,
+        max_liquidity: f64,
+        max_reserve_ratio: f64,
+    ) -> std::result::Result<(), PoolSkipReason> {
         // Vérifier la liquidité
         if pool.liquidity_usd < min_liquidity || pool.liquidity_usd > max_liquidity {
-            return false;
+            return Err(PoolSkipReason::LiquidityOutOfRange { liquidity_usd: pool.liquidity_usd });
         }
 
         // Vérifier que les réserves sont suffisantes
         if pool.reserve_a == 0 || pool.reserve_b == 0 {
-            return false;
+            return Err(PoolSkipReason::EmptyReserves);
+        }
+
+        // Vérifier que les deux côtés du pool ne sont pas totalement déséquilibrés
+        let ratio = self.reserve_value_ratio(pool);
+        if ratio > max_reserve_ratio {
+            return Err(PoolSkipReason::ReserveImbalance { ratio });
         }
 
         // Vérifier le market cap si disponible
         if let Some(mcap) = pool.market_cap_usd {
             // Éviter les tokens avec un mcap trop faible (probable scam) ou trop élevé (pas rentable)
-            if mcap < 10_000.0 || mcap > 10_000_000.0 {
-                return false;
+            if !(10_000.0..=10_000_000.0).contains(&mcap) {
+                return Err(PoolSkipReason::McapOutOfRange { mcap_usd: mcap });
             }
         }
 
-        true
+        Ok(())
     }
 
-    /// Calcule l'impact sur le prix d'un swap
-    pub fn calculate_price_impact(&self, pool: &PoolInfo, amount_in: u64, is_a_to_b: bool) -> f64 {
+    /// Ratio (>= 1) entre la valeur du côté le plus fourni et celle du côté le plus pauvre.
+    /// Utilise la valeur USD de chaque côté quand les deux prix sont connus, sinon les montants UI.
+    fn reserve_value_ratio(&self, pool: &PoolInfo) -> f64 {
+        let side_price = |mint: &Pubkey| self.quote_usd_price(mint).or(pool.token_price_usd);
+
+        let (value_a, value_b) = match (side_price(&pool.token_a_mint), side_price(&pool.token_b_mint)) {
+            (Some(price_a), Some(price_b)) => (pool.token_a_liquidity * price_a, pool.token_b_liquidity * price_b),
+            _ => (pool.token_a_liquidity, pool.token_b_liquidity),
+        };
+
+        let (high, low) = if value_a >= value_b { (value_a, value_b) } else { (value_b, value_a) };
+        if low <= 0.0 {
+            return f64::INFINITY;
+        }
+        high / low
+    }
+
+    /// Calcule l'impact sur le prix d'un swap (en %), frais du pool inclus si `fee_adjusted`
+    pub fn calculate_price_impact(&self, pool: &PoolInfo, amount_in: u64, is_a_to_b: bool, fee_adjusted: bool, dynamic_fee: bool) -> f64 {
         let (reserve_in, reserve_out) = if is_a_to_b {
-            (pool.reserve_a, pool.reserve_b)
+            (pool.reserve_a as f64, pool.reserve_b as f64)
         } else {
-            (pool.reserve_b, pool.reserve_a)
+            (pool.reserve_b as f64, pool.reserve_a as f64)
         };
 
-        if reserve_in == 0 || reserve_out == 0 {
+        if reserve_in == 0.0 || reserve_out == 0.0 {
             return 0.0;
         }
 
-        // Formule AMM : x * y = k
-        let k = (reserve_in as f64) * (reserve_out as f64);
-        let new_reserve_in = reserve_in as f64 + amount_in as f64;
-        let new_reserve_out = k / new_reserve_in;
-        
-        let price_before = reserve_out as f64 / reserve_in as f64;
+        let fee_bps = if fee_adjusted { pool.fee_bps } else { 0 };
+
+        // DLMM avec bins connus : le prix marche sur la liquidité réelle des bins
+        if let (Some(bins), Some(active_id), Some(bin_step)) = (&pool.dlmm_bins, pool.tick_current, pool.bin_step) {
+            let (_amount_out, mut end_bin) = dlmm_swap_out(bins, active_id, bin_step, amount_in as f64, is_a_to_b, fee_bps);
+            // Frais dynamiques : ré-simuler avec le frais effectif sur les bins traversés
+            if let (true, true, Some(fees)) = (fee_adjusted, dynamic_fee, &pool.dlmm_fee) {
+                let effective = fees.effective_fee_bps(bin_step, active_id, end_bin, chrono::Utc::now().timestamp());
+                end_bin = dlmm_swap_out(bins, active_id, bin_step, amount_in as f64, is_a_to_b, effective.round() as u16).1;
+            }
+            let price_ratio = dlmm_bin_price(end_bin, bin_step) / dlmm_bin_price(active_id, bin_step);
+            return (price_ratio - 1.0).abs() * 100.0;
+        }
+
+        let mut amount_out = amm_out_with_fee(reserve_in, reserve_out, amount_in as f64, fee_bps);
+
+        // Frais dynamiques sans bins : estimer le nombre de bins traversés depuis l'impact x*y=k
+        if let (true, true, Some(fees), Some(active_id), Some(bin_step)) =
+            (fee_adjusted, dynamic_fee, &pool.dlmm_fee, pool.tick_current, pool.bin_step)
+        {
+            let price_move = (reserve_out / reserve_in) / ((reserve_out - amount_out) / (reserve_in + amount_in as f64));
+            let bins_crossed = (price_move.ln() / (1.0 + bin_step as f64 / 10_000.0).ln()).round() as i32;
+            let end_bin = if is_a_to_b { active_id - bins_crossed } else { active_id + bins_crossed };
+            let effective = fees.effective_fee_bps(bin_step, active_id, end_bin, chrono::Utc::now().timestamp());
+            amount_out = amm_out_with_fee(reserve_in, reserve_out, amount_in as f64, effective.round() as u16);
+        }
+
+        // Les frais restent dans le pool : la réserve d'entrée augmente du montant brut
+        let new_reserve_in = reserve_in + amount_in as f64;
+        let new_reserve_out = reserve_out - amount_out;
+
+        let price_before = reserve_out / reserve_in;
         let price_after = new_reserve_out / new_reserve_in;
-        
-        let impact = ((price_after - price_before) / price_before).abs() * 100.0;
-        impact
+
+        ((price_after - price_before) / price_before).abs() * 100.0
+    }
+}
+
+// ============================================================================
+// VALIDATION DES COMPTES
+// ============================================================================
+
+/// Taille minimale avant désérialisation : un compte d'un autre layout (ex. CLMM passé au parser V4)
+/// ou une réponse tronquée donnerait sinon une erreur Borsh confuse, voire des valeurs absurdes.
+/// Retourne les `len` premiers octets : `try_from_slice` exige la taille exacte, un compte plus long
+/// (champs ajoutés en fin de layout) reste décodable.
+fn check_account_len<'a>(data: &'a [u8], len: usize, pool_id: &Pubkey, dex_type: DexType) -> Result<&'a [u8]> {
+    data.get(..len).ok_or_else(|| {
+        anyhow!("compte {} trop court pour {:?}: {} octets, {} attendus", pool_id, dex_type, data.len(), len)
+    })
+}
+
+/// Montant d'un token account. Token-2022 : le layout SPL de base est en tête du compte, suivi des extensions
+pub fn token_account_amount(account: Option<&Account>, key: &Pubkey) -> Result<u64> {
+    let data = account.map(|a| a.data.as_slice())
+        .ok_or_else(|| anyhow!("Token account {} introuvable", key))?;
+    let state = data.get(..TokenAccount::LEN)
+        .map(TokenAccount::unpack_from_slice)
+        .ok_or_else(|| anyhow!("Token account {} trop court: {} octets", key, data.len()))??;
+    Ok(state.amount)
+}
+
+/// Le compte doit appartenir au programme du DEX attendu
+pub fn check_account_owner(account: &Account, pool_id: &Pubkey, dex_type: &DexType, program_id: &Pubkey) -> Result<()> {
+    if account.owner != *program_id {
+        return Err(anyhow!(
+            "compte {} détenu par {} et non par le programme {:?} attendu ({})",
+            pool_id, account.owner, dex_type, program_id
+        ));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// MATHS AMM (x * y = k)
+// ============================================================================
+
+/// Prix d'un bin DLMM (Y par X, unités brutes) : (1 + bin_step / 10000) ^ bin_id
+pub fn dlmm_bin_price(bin_id: i32, bin_step: u16) -> f64 {
+    (1.0 + bin_step as f64 / 10_000.0).powi(bin_id)
+}
+
+/// Simule un swap DLMM bin par bin à partir du bin actif (frais prélevés sur l'entrée).
+/// X -> Y consomme le Y des bins en descendant, Y -> X le X des bins en montant.
+/// Retourne (montant reçu, dernier bin touché) ; s'arrête au bord des bins chargés.
+pub fn dlmm_swap_out(
+    bins: &[DlmmBin],
+    active_id: i32,
+    bin_step: u16,
+    amount_in: f64,
+    x_to_y: bool,
+    fee_bps: u16,
+) -> (f64, i32) {
+    let mut remaining = amount_in * (10_000.0 - fee_bps as f64) / 10_000.0;
+    let mut amount_out = 0.0;
+    let mut end_bin = active_id;
+
+    let walk: Box<dyn Iterator<Item = &DlmmBin>> = if x_to_y {
+        Box::new(bins.iter().rev().filter(|bin| bin.id <= active_id))
+    } else {
+        Box::new(bins.iter().filter(|bin| bin.id >= active_id))
+    };
+
+    for bin in walk {
+        if remaining <= 0.0 {
+            break;
+        }
+        let price = dlmm_bin_price(bin.id, bin_step);
+        // Liquidité de sortie du bin et entrée nécessaire pour la vider
+        let (available_out, in_to_drain) = if x_to_y {
+            (bin.amount_y as f64, bin.amount_y as f64 / price)
+        } else {
+            (bin.amount_x as f64, bin.amount_x as f64 * price)
+        };
+        if available_out <= 0.0 {
+            continue;
+        }
+
+        end_bin = bin.id;
+        if remaining >= in_to_drain {
+            amount_out += available_out;
+            remaining -= in_to_drain;
+        } else {
+            amount_out += if x_to_y { remaining * price } else { remaining / price };
+            remaining = 0.0;
+        }
+    }
+
+    (amount_out, end_bin)
+}
+
+/// Montant reçu pour `amount_in` sur un pool x*y=k, frais prélevés sur l'entrée
+pub fn amm_out_with_fee(reserve_in: f64, reserve_out: f64, amount_in: f64, fee_bps: u16) -> f64 {
+    if reserve_in <= 0.0 || reserve_out <= 0.0 || amount_in <= 0.0 {
+        return 0.0;
+    }
+    let amount_in_after_fee = amount_in * (10_000.0 - fee_bps as f64) / 10_000.0;
+    reserve_out * amount_in_after_fee / (reserve_in + amount_in_after_fee)
+}
+
+/// Montant brut (frais inclus) à envoyer pour recevoir `amount_out` sur un pool x*y=k.
+/// None si `amount_out` vide (ou dépasse) la réserve de sortie.
+pub fn amm_in_with_fee(reserve_in: f64, reserve_out: f64, amount_out: f64, fee_bps: u16) -> Option<f64> {
+    if reserve_in <= 0.0 || amount_out >= reserve_out || fee_bps >= 10_000 {
+        return None;
     }
+    let amount_in_after_fee = reserve_in * amount_out / (reserve_out - amount_out);
+    Some(amount_in_after_fee * 10_000.0 / (10_000.0 - fee_bps as f64))
 }
 
+/// Convertit un sqrt_price Q64.64 (Orca Whirlpool, Raydium CLMM) en prix du token A exprimé
+/// en token B, en unités UI (ajusté par 10^(decimals_a - decimals_b))
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_a: u8, decimals_b: u8) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / 2f64.powi(64);
+    sqrt_price * sqrt_price * 10f64.powi(decimals_a as i32 - decimals_b as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        mint_account, pool_info, raydium_amm_config_account, raydium_clmm_account_data, raydium_v4_account_data,
+        token_account, usdc, whirlpool_account_data, wsol, FakeRpc,
+    };
+
+    fn parser() -> PoolParser {
+        PoolParser::new(Arc::new(AsyncRpcClient::new_mock("fails".to_string())))
+    }
+
+    #[test]
+    fn imbalanced_pool_is_rejected_with_its_own_reason() {
+        let token = Pubkey::new_unique();
+        // 1 USDC face à 1 milliard de tokens à 1 $ : ratio de valeur 1:1 000 000 000
+        let mut pool = pool_info(usdc(), token, 1_000_000, 1_000_000_000_000_000, 6, 6);
+        pool.liquidity_usd = 5_000.0;
+        pool.token_price_usd = Some(1.0);
+
+        let result = parser().check_pool_validity(&pool, 1_000.0, 1e12, 1_000_000.0);
+        match result {
+            Err(PoolSkipReason::ReserveImbalance { ratio }) => assert!((ratio - 1e9).abs() / 1e9 < 1e-9),
+            other => panic!("ReserveImbalance attendu, obtenu {:?}", other),
+        }
+
+        // Même pool équilibrée : acceptée
+        pool.reserve_b = 1_000_000;
+        pool.token_b_liquidity = 1.0;
+        assert!(parser().check_pool_validity(&pool, 1_000.0, 1e12, 1_000_000.0).is_ok());
+    }
+
+    #[test]
+    fn orca_synthetic_sol_usdc_whirlpool_price_from_sqrt_price() {
+        // Compte synthétique (layout publié, pas un dump mainnet) : Whirlpool SOL/USDC tick spacing 64,
+        // sqrt_price d'un SOL à 150 $ : √(150 × 10^(6-9)) en Q64.64
+        let data = whirlpool_account_data(&wsol(), &Pubkey::new_unique(), &usdc(), &Pubkey::new_unique(), 7_144_393_258_922_745_856, 64, 400);
+
+        let whirlpool = OrcaWhirlpoolInfo::from_account_data(&data).unwrap();
+        assert_eq!(whirlpool.token_mint_a, wsol());
+        assert_eq!(whirlpool.token_mint_b, usdc());
+        assert_eq!(whirlpool.tick_spacing, 64);
+        assert_eq!(whirlpool.fee_bps(), 4);
+
+        let price = sqrt_price_x64_to_price(whirlpool.sqrt_price, 9, 6);
+        assert!((price - 150.0).abs() / 150.0 < 0.02, "prix SOL/USDC {}", price);
+    }
+
+    #[test]
+    fn orca_rejects_other_program_accounts() {
+        let mut data = whirlpool_account_data(&wsol(), &Pubkey::new_unique(), &usdc(), &Pubkey::new_unique(), 1 << 64, 64, 3000);
+        data[0] ^= 0xff;
+        assert!(OrcaWhirlpoolInfo::from_account_data(&data).is_err());
+    }
+
+    #[tokio::test]
+    async fn orca_reserves_come_from_vaults_and_price_from_sqrt_price() {
+        let rpc = FakeRpc::new();
+        let (token, pool_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (vault_a, vault_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(usdc(), mint_account(6, 0));
+        // Ratio des vaults (0,02 $) volontairement éloigné du prix spot (0,5 $)
+        rpc.add_account(vault_a, token_account(&token, &pool_id, 500_000_000));
+        rpc.add_account(vault_b, token_account(&usdc(), &pool_id, 10_000_000));
+
+        let data = whirlpool_account_data(&token, &vault_a, &usdc(), &vault_b, 13_043_817_825_332_783_104, 8, 3000);
+        let parser = PoolParser::new(rpc.client());
+        let program_id = Pubkey::from_str(crate::types::ORCA_WHIRLPOOL_PROGRAM).unwrap();
+        let pool = parser.parse_pool_account(&data, &pool_id, DexType::OrcaWhirlpool, program_id).await.unwrap();
+
+        assert_eq!((pool.reserve_a, pool.reserve_b), (500_000_000, 10_000_000));
+        assert_eq!(pool.fee_bps, 30);
+        let price = pool.token_price_usd.unwrap();
+        assert!((price - 0.5).abs() < 1e-6, "prix {}", price);
+        assert!((pool.market_cap_usd.unwrap() - 500_000.0).abs() < 1.0);
+        assert_eq!(rpc.count("getMultipleAccounts"), 1);
+        assert_eq!(rpc.count("getAccountInfo"), 0);
+    }
+
+    #[tokio::test]
+    async fn orca_vault_verification_rejects_vaults_of_other_mints() {
+        let rpc = FakeRpc::new();
+        let (token, pool_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (vault_a, vault_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(usdc(), mint_account(6, 0));
+        rpc.add_account(vault_a, token_account(&token, &pool_id, 500_000_000));
+        // Vault B décodé au mauvais offset : token account d'un autre mint
+        rpc.add_account(vault_b, token_account(&Pubkey::new_unique(), &pool_id, 10_000_000));
+
+        let data = whirlpool_account_data(&token, &vault_a, &usdc(), &vault_b, 1 << 64, 8, 3000);
+        let program_id = Pubkey::from_str(crate::types::ORCA_WHIRLPOOL_PROGRAM).unwrap();
+        let mut parser = PoolParser::new(rpc.client());
+        assert!(parser.parse_pool_account(&data, &pool_id, DexType::OrcaWhirlpool, program_id).await.is_ok());
+
+        parser.verify_orca_vaults = true;
+        assert!(parser.parse_pool_account(&data, &pool_id, DexType::OrcaWhirlpool, program_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cached_decimals_come_from_parsed_mints_without_rpc() {
+        let rpc = FakeRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        let parser = PoolParser::new(rpc.client());
+
+        assert_eq!(parser.cached_decimals(&wsol()).await, Some(9));
+        assert_eq!(parser.cached_decimals(&token).await, None);
+        assert_eq!(parser.get_mint_decimals(&token).await.unwrap(), 6);
+        assert_eq!(parser.cached_decimals(&token).await, Some(6));
+        assert_eq!(rpc.count("getAccountInfo"), 1);
+    }
+
+    #[tokio::test]
+    async fn six_decimal_mint_gives_price_and_mcap_in_ui_units() {
+        let rpc = FakeRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000)); // 1 000 000 tokens
+        let mut parser = PoolParser::new(rpc.client());
+        parser.set_sol_price(150.0);
+
+        // 1 000 tokens (6 décimales) face à 10 SOL (9 décimales) : 0,01 SOL = 1,5 $ le token
+        let (liquidity_usd, token_liquidity, _, mcap, price, supply) = parser
+            .calculate_pool_metrics(&token, &wsol(), 1_000_000_000, 10_000_000_000, None)
+            .await
+            .unwrap();
+        assert_eq!(token_liquidity, 1_000.0);
+        assert!((price.unwrap() - 1.5).abs() < 1e-9, "prix {:?}", price);
+        assert!((mcap.unwrap() - 1_500_000.0).abs() < 1e-3, "mcap {:?}", mcap);
+        assert!((liquidity_usd - 3_000.0).abs() < 1e-9);
+        assert_eq!(supply, Some(1_000_000_000_000));
+    }
+
+    #[tokio::test]
+    async fn raydium_v4_account_of_official_size_decodes() {
+        let rpc = FakeRpc::new();
+        let (token, pool_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(wsol(), mint_account(9, 0));
+        rpc.add_account(base_vault, token_account(&token, &pool_id, 1_000_000_000));
+        rpc.add_account(quote_vault, token_account(&wsol(), &pool_id, 10_000_000_000));
+
+        let data = raydium_v4_account_data(&token, &base_vault, &wsol(), &quote_vault, (6, 9));
+        assert_eq!(data.len(), RaydiumAmmInfo::LEN);
+        let amm_info = RaydiumAmmInfo::try_from_slice(&data).unwrap();
+        assert_eq!((amm_info.base_mint, amm_info.quote_mint), (token, wsol()));
+        assert_eq!((amm_info.lp_amount, amm_info.client_order_id), (1_000_000, 42));
+
+        let mut parser = PoolParser::new(rpc.client());
+        parser.set_sol_price(150.0);
+        let program_id = Pubkey::from_str(crate::types::RAYDIUM_V4_PROGRAM).unwrap();
+        let pool = parser.parse_pool_account(&data, &pool_id, DexType::RaydiumV4, program_id).await.unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_000_000, 10_000_000_000));
+        assert_eq!(pool.fee_bps, 25);
+        assert!((pool.token_price_usd.unwrap() - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn amm_out_and_in_with_fee_match_hand_computed_values() {
+        // 100 entrés sur 1000/2000 à 30 bps : 99,7 après frais, 2000 * 99,7 / 1099,7 en sortie
+        let out = amm_out_with_fee(1_000.0, 2_000.0, 100.0, 30);
+        assert!((out - 181.32217877602983).abs() < 1e-9, "sortie {}", out);
+        assert!((amm_out_with_fee(1_000.0, 2_000.0, 100.0, 0) - 2_000.0 * 100.0 / 1_100.0).abs() < 1e-9);
+
+        // Inverse : le montant à envoyer pour recevoir cette sortie est l'entrée d'origine
+        let amount_in = amm_in_with_fee(1_000.0, 2_000.0, out, 30).unwrap();
+        assert!((amount_in - 100.0).abs() < 1e-9, "entrée {}", amount_in);
+
+        assert_eq!(amm_out_with_fee(0.0, 2_000.0, 100.0, 30), 0.0);
+        assert_eq!(amm_in_with_fee(1_000.0, 2_000.0, 2_000.0, 30), None);
+        assert_eq!(amm_in_with_fee(1_000.0, 2_000.0, 100.0, 10_000), None);
+    }
+
+    #[test]
+    fn sqrt_price_x64_converts_to_known_prices() {
+        // Q64.64 : 2^64 vaut 1, 2^65 vaut 2 (prix 4), 2^63 vaut 0,5 (prix 0,25)
+        assert_eq!(sqrt_price_x64_to_price(1 << 64, 6, 6), 1.0);
+        assert_eq!(sqrt_price_x64_to_price(1 << 65, 6, 6), 4.0);
+        assert_eq!(sqrt_price_x64_to_price(1 << 63, 6, 6), 0.25);
+        // Prix brut 1 entre un mint à 9 décimales et un à 6 : 1000 en unités UI
+        assert!((sqrt_price_x64_to_price(1 << 64, 9, 6) - 1_000.0).abs() < 1e-9);
+        // SOL/USDC : √(150 × 10^-3) × 2^64
+        assert!((sqrt_price_x64_to_price(7_144_393_258_922_745_856, 9, 6) - 150.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn dlmm_bin_price_for_known_active_bins() {
+        assert_eq!(dlmm_bin_price(0, 25), 1.0);
+        assert!((dlmm_bin_price(100, 25) - 1.283624888738461).abs() < 1e-12);
+        assert!((dlmm_bin_price(-100, 25) * dlmm_bin_price(100, 25) - 1.0).abs() < 1e-12);
+
+        // SOL/USDC à bin_step 10 : bin actif -1899, 1.001^-1899 × 10^(9-6) ≈ 149,86 $
+        let price = dlmm_bin_price(-1_899, 10) * 10f64.powi(9 - 6);
+        assert!((price - 149.86039286158484).abs() < 1e-6, "prix {}", price);
+    }
+
+    /// Pool Raydium V4 token/WSOL dont les comptes sont servis par `rpc` : (compte du pool, id du pool)
+    fn raydium_v4_pool(rpc: &FakeRpc) -> (Vec<u8>, Pubkey) {
+        let (token, pool_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(base_vault, token_account(&token, &pool_id, 1_000_000_000));
+        rpc.add_account(quote_vault, token_account(&wsol(), &pool_id, 10_000_000_000));
+        (raydium_v4_account_data(&token, &base_vault, &wsol(), &quote_vault, (6, 9)), pool_id)
+    }
+
+    #[tokio::test]
+    async fn batched_pool_parse_takes_one_round_trip() {
+        let program_id = Pubkey::from_str(crate::types::RAYDIUM_V4_PROGRAM).unwrap();
+
+        // Sans batch : vaults groupés, puis décimales et supply du mint lus un par un
+        let rpc = FakeRpc::new();
+        let (data, pool_id) = raydium_v4_pool(&rpc);
+        let mut parser = PoolParser::new(rpc.client());
+        parser.batch_account_fetches = false;
+        parser.parse_pool_account(&data, &pool_id, DexType::RaydiumV4, program_id).await.unwrap();
+        let unbatched = rpc.count("getMultipleAccounts") + rpc.count("getAccountInfo") + rpc.count("getTokenSupply");
+        assert_eq!(unbatched, 3);
+
+        // Avec batch : vaults et mints dans un seul getMultipleAccounts
+        let rpc = FakeRpc::new();
+        let (data, pool_id) = raydium_v4_pool(&rpc);
+        let parser = PoolParser::new(rpc.client());
+        parser.parse_pool_account(&data, &pool_id, DexType::RaydiumV4, program_id).await.unwrap();
+        assert_eq!(rpc.count("getMultipleAccounts"), 1);
+        assert_eq!(rpc.count("getAccountInfo") + rpc.count("getTokenSupply"), 0);
+    }
+
+    #[tokio::test]
+    async fn clmm_amm_config_is_fetched_with_the_vaults() {
+        let rpc = FakeRpc::new();
+        let (token, pool_id, amm_config) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(vault_0, token_account(&wsol(), &pool_id, 10_000_000_000));
+        rpc.add_account(vault_1, token_account(&token, &pool_id, 1_000_000_000));
+        rpc.add_account(amm_config, raydium_amm_config_account(2_500));
+
+        let data = raydium_clmm_account_data(&amm_config, (&wsol(), &vault_0, 9), (&token, &vault_1, 6), 1 << 64);
+        let program_id = Pubkey::from_str(crate::types::RAYDIUM_CLMM_PROGRAM).unwrap();
+        let pool = PoolParser::new(rpc.client())
+            .parse_pool_account(&data, &pool_id, DexType::RaydiumCLMM, program_id)
+            .await
+            .unwrap();
+        assert_eq!(pool.fee_bps, 25);
+        assert_eq!(rpc.count("getMultipleAccounts"), 1);
+        assert_eq!(rpc.count("getAccountInfo"), 0);
+    }
+
+    #[tokio::test]
+    async fn token_balances_batch_keeps_order_and_reads_token_2022_accounts() {
+        let rpc = FakeRpc::new();
+        let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for (key, amount) in keys.iter().zip([30, 10, 20]) {
+            let mut account = token_account(&wsol(), &Pubkey::new_unique(), amount);
+            // Token-2022 : type de compte et extensions après les 165 octets du layout de base
+            account.data.extend_from_slice(&[2, 0, 7, 0, 1, 0, 0]);
+            rpc.add_account(*key, account);
+        }
+
+        let balances = PoolParser::new(rpc.client()).get_token_balances_batch(&keys).await.unwrap();
+        assert_eq!(balances, vec![30, 10, 20]);
+        assert_eq!(rpc.count("getMultipleAccounts"), 1);
+        assert!(PoolParser::new(rpc.client()).get_token_balances_batch(&[Pubkey::new_unique()]).await.is_err());
+    }
+
+    #[test]
+    fn account_len_constants_match_borsh_sizes() {
+        fn borsh_len<T: BorshDeserialize + borsh::BorshSerialize>(len: usize) -> usize {
+            T::try_from_slice(&vec![0u8; len]).expect("taille exacte").try_to_vec().unwrap().len()
+        }
+        assert_eq!(borsh_len::<RaydiumAmmInfo>(RaydiumAmmInfo::LEN), RaydiumAmmInfo::LEN);
+        assert_eq!(borsh_len::<LifinityPoolInfo>(LifinityPoolInfo::LEN), LifinityPoolInfo::LEN);
+        assert_eq!(borsh_len::<PhoenixMarketInfo>(PhoenixMarketInfo::LEN), PhoenixMarketInfo::LEN);
+        assert_eq!(borsh_len::<SerumMarketInfo>(SerumMarketInfo::LEN), SerumMarketInfo::LEN);
+    }
+
+    #[test]
+    fn account_len_check_returns_the_layout_prefix() {
+        let pool_id = Pubkey::new_unique();
+        let data = [0u8; SerumMarketInfo::LEN + 16];
+
+        // Taille exacte et compte plus long : préfixe du layout, décodable
+        for len in [SerumMarketInfo::LEN, data.len()] {
+            let prefix = check_account_len(&data[..len], SerumMarketInfo::LEN, &pool_id, DexType::Serum).unwrap();
+            assert_eq!(prefix.len(), SerumMarketInfo::LEN);
+            assert!(SerumMarketInfo::try_from_slice(prefix).is_ok());
+        }
+
+        // Compte tronqué : erreur lisible plutôt qu'une erreur Borsh
+        let error = check_account_len(&data[..100], SerumMarketInfo::LEN, &pool_id, DexType::Serum).unwrap_err();
+        assert_eq!(error.to_string(), format!("compte {} trop court pour Serum: 100 octets, 152 attendus", pool_id));
+    }
+
+    #[tokio::test]
+    async fn truncated_raydium_account_fails_with_a_length_error() {
+        let rpc = FakeRpc::new();
+        let (data, pool_id) = raydium_v4_pool(&rpc);
+        let program_id = Pubkey::from_str(crate::types::RAYDIUM_V4_PROGRAM).unwrap();
+        let error = PoolParser::new(rpc.client())
+            .parse_pool_account(&data[..600], &pool_id, DexType::RaydiumV4, program_id)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("trop court"), "{:#}", error);
+    }
+
+    #[tokio::test]
+    async fn raydium_clmm_synthetic_account_decodes_at_published_offsets() {
+        // Compte synthétique écrit d'après le layout publié, pas un dump mainnet : vérifie le décodeur
+        // contre les offsets documentés, pas contre un PoolState réel
+        let rpc = FakeRpc::new();
+        let (token, pool_id, amm_config) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // sqrt_price 2^63 : 0,25 USDC le token (décimales égales)
+        let data = raydium_clmm_account_data(&amm_config, (&token, &vault_0, 6), (&usdc(), &vault_1, 6), 1 << 63);
+
+        assert_eq!(&data[RaydiumClmmInfo::MINT_0_OFFSET..RaydiumClmmInfo::MINT_0_OFFSET + 32], token.as_ref());
+        assert_eq!(&data[RaydiumClmmInfo::MINT_1_OFFSET..RaydiumClmmInfo::MINT_1_OFFSET + 32], usdc().as_ref());
+        let clmm = RaydiumClmmInfo::from_account_data(&data).unwrap();
+        assert_eq!((clmm.amm_config, clmm.token_mint_0, clmm.token_mint_1), (amm_config, token, usdc()));
+        assert_eq!((clmm.token_vault_0, clmm.token_vault_1), (vault_0, vault_1));
+        assert_eq!((clmm.mint_decimals_0, clmm.mint_decimals_1, clmm.tick_spacing), (6, 6, 60));
+        assert_eq!(clmm.sqrt_price_x64, 1 << 63);
+        assert!(RaydiumClmmInfo::from_account_data(&data[..RaydiumClmmInfo::MIN_LEN]).is_ok());
+        assert!(RaydiumClmmInfo::from_account_data(&data[..RaydiumClmmInfo::MIN_LEN - 1]).is_err());
+
+        rpc.add_account(token, mint_account(6, 1_000_000_000_000));
+        rpc.add_account(vault_0, token_account(&token, &pool_id, 4_000_000_000));
+        rpc.add_account(vault_1, token_account(&usdc(), &pool_id, 1_000_000_000));
+        rpc.add_account(amm_config, raydium_amm_config_account(500));
+        let program_id = Pubkey::from_str(crate::types::RAYDIUM_CLMM_PROGRAM).unwrap();
+        let parser = PoolParser::new(rpc.client());
+        let pool = parser.parse_pool_account(&data, &pool_id, DexType::RaydiumCLMM, program_id).await.unwrap();
+        assert_eq!(pool.fee_bps, 5);
+        assert!((pool.token_price_usd.unwrap() - 0.25).abs() < 1e-12);
+        assert!((pool.market_cap_usd.unwrap() - 250_000.0).abs() < 1e-6);
+        assert_eq!(parser.cached_decimals(&token).await, Some(6));
+    }
+}
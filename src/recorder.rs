@@ -0,0 +1,139 @@
+use crate::types::SandwichAnalysisResult;
+use anyhow::{anyhow, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+// ============================================================================
+// EXPORT CSV DES ANALYSES
+// ============================================================================
+
+const CSV_HEADER: &str = "signature,invested_amount,tokens_received,mcap_before,mcap_after,mcap_impact,execution_time_ms,is_sandwich_opportunity,estimated_profit";
+
+/// Accumule les résultats d'analyse et les ajoute au CSV `path` toutes les `flush_every` lignes
+pub struct AnalysisRecorder {
+    path: PathBuf,
+    flush_every: usize,
+    pending: Vec<String>,
+}
+
+impl AnalysisRecorder {
+    pub fn new(path: PathBuf, flush_every: usize) -> Self {
+        Self {
+            path,
+            flush_every: flush_every.max(1),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Ajoute un résultat ; écrit le lot dès que `flush_every` lignes sont en attente
+    pub fn record(&mut self, result: &SandwichAnalysisResult) -> Result<()> {
+        self.pending.push(format!(
+            "{},{},{},{},{},{},{},{},{}",
+            result.signature,
+            result.invested_amount,
+            result.tokens_received,
+            result.mcap_before,
+            result.mcap_after,
+            result.mcap_impact,
+            result.execution_time.as_millis(),
+            result.is_sandwich_opportunity,
+            result.estimated_profit,
+        ));
+        if self.pending.len() >= self.flush_every {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Écrit les lignes en attente (en-tête ajouté si le fichier est vide)
+    pub fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| anyhow!("Ouverture de {} impossible: {}", self.path.display(), e))?;
+
+        let mut text = String::new();
+        if file.metadata()?.len() == 0 {
+            text.push_str(CSV_HEADER);
+            text.push('\n');
+        }
+        for line in self.pending.drain(..) {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        file.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Drop for AnalysisRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            log::warn!("⚠️ Export CSV des analyses incomplet: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TransactionClassification;
+    use std::time::Duration;
+
+    fn result(signature: &str, mcap_impact: f64, is_sandwich_opportunity: bool) -> SandwichAnalysisResult {
+        SandwichAnalysisResult {
+            signature: signature.to_string(),
+            invested_amount: 250.5,
+            tokens_received: 12_345.678,
+            mcap_before: 1_000_000.0,
+            mcap_after: 1_000_000.0 * (1.0 + mcap_impact / 100.0),
+            mcap_impact,
+            execution_time: Duration::from_millis(42),
+            is_sandwich_opportunity,
+            estimated_profit: 3.5,
+            classification: TransactionClassification::Swap,
+            tokens_received_estimated: false,
+            parties: None,
+            debug_detail: None,
+        }
+    }
+
+    #[test]
+    fn recorded_results_read_back_from_the_csv() {
+        let path = std::env::temp_dir().join(format!("sandwich-bot-analyses-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut recorder = AnalysisRecorder::new(path.clone(), 2);
+        recorder.record(&result("sig-1", 2.5, true)).unwrap();
+        assert!(!path.exists(), "écrit avant flush_every lignes");
+        recorder.record(&result("sig-2", 0.1, false)).unwrap();
+        recorder.record(&result("sig-3", 7.0, true)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+        // La ligne en attente est écrite au drop, sans répéter l'en-tête
+        drop(recorder);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == CSV_HEADER.split(',').count()));
+
+        let signatures: Vec<&str> = rows.iter().map(|row| row[0]).collect();
+        assert_eq!(signatures, ["sig-1", "sig-2", "sig-3"]);
+        let first = &rows[0];
+        assert_eq!(first[1].parse::<f64>().unwrap(), 250.5);
+        assert_eq!(first[2].parse::<f64>().unwrap(), 12_345.678);
+        assert_eq!(first[5].parse::<f64>().unwrap(), 2.5);
+        assert_eq!(first[6].parse::<u128>().unwrap(), 42);
+        assert!(first[7].parse::<bool>().unwrap());
+        assert!(!rows[1][7].parse::<bool>().unwrap());
+        assert_eq!(first[8].parse::<f64>().unwrap(), 3.5);
+    }
+}
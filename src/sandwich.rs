@@ -1,5 +1,6 @@
 use crate::config::BotConfig;
 use crate::monitoring::MonitoringEngine;
+use crate::pool_parser::{amm_out_with_fee, PoolParser};
 use crate::types::{
     DexType, PoolInfo, ParsedSwap, ProfitAnalysis, SwapSimulation, TransactionLog,
 };
@@ -149,8 +150,25 @@ impl SandwichEngine {
         Ok(bundle)
     }
 
-    /// Soumet le bundle avec retry automatique
+    /// Soumet le bundle avec retry automatique. En `test_mode`, rien n'est jamais envoyé :
+    /// signature factice `DRYRUN-<hash>` sans aucun appel RPC.
     async fn submit_bundle_with_retry(&self, bundle: Vec<Transaction>) -> Result<String> {
+        if self.config.test_mode {
+            let hash = bundle.first()
+                .map(|tx| tx.message.hash().to_string())
+                .ok_or_else(|| anyhow!("Bundle vide"))?;
+            log::info!("🧪 Mode test : bundle de {} transaction(s) non envoyé (DRYRUN-{})", bundle.len(), hash);
+            // Transactions sérialisées telles qu'elles partiraient au block engine, pour les rejouer/inspecter
+            for (index, tx) in bundle.iter().enumerate() {
+                let signatures: Vec<String> = tx.signatures.iter().map(|signature| signature.to_string()).collect();
+                log::info!(
+                    "🧪 Tx {}/{} | signatures: [{}] | base64: {}",
+                    index + 1, bundle.len(), signatures.join(", "), Self::encode_bundle_transaction(tx)?
+                );
+            }
+            return Ok(format!("DRYRUN-{}", hash));
+        }
+
         let max_retries = 3;
         let mut retry_count = 0;
         
@@ -172,6 +190,13 @@ impl SandwichEngine {
         Err(anyhow!("Échec après {} tentatives", max_retries))
     }
 
+    /// Transaction sérialisée (bincode) en base64, le format attendu par `sendBundle` / `simulateTransaction`
+    fn encode_bundle_transaction(tx: &Transaction) -> Result<String> {
+        use base64::Engine;
+        let bytes = bincode::serialize(tx).map_err(|e| anyhow!("Sérialisation de la transaction impossible: {}", e))?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
     /// Essaie de soumettre le bundle
     async fn try_submit_bundle(&self, bundle: &[Transaction]) -> Result<String> {
         // Soumettre la première transaction (front-run)
@@ -281,15 +306,11 @@ impl SandwichEngine {
     pub async fn calculate_profit_for_swap(&self, swap: &ParsedSwap) -> Result<SwapSimulation> {
         match swap.pool.dex_type {
             DexType::RaydiumV4 => {
-                // TODO: Implémenter le calcul pour Raydium V4
-                Ok(SwapSimulation {
-                    tokens_out: 0,
-                    tokens_out_min: 0,
-                    price_impact_bps: 0,
-                })
+                // AMM produit constant
+                Ok(self.simulate_amm_swap(&swap.pool, swap.amount_in, swap.a_to_b))
             }
-            DexType::OrcaWhirlpool => {
-                // TODO: Implémenter le calcul pour Orca Whirlpool
+            DexType::OrcaWhirlpool | DexType::RaydiumCLMM => {
+                // TODO: Implémenter le calcul pour la liquidité concentrée (Orca Whirlpool, Raydium CLMM)
                 Ok(SwapSimulation {
                     tokens_out: 0,
                     tokens_out_min: 0,
@@ -320,8 +341,8 @@ impl SandwichEngine {
                     price_impact_bps: 0,
                 })
             }
-            DexType::Serum => {
-                // TODO: Implémenter le calcul pour Serum
+            DexType::Serum | DexType::OpenBookV2 => {
+                // TODO: Implémenter le calcul pour les carnets d'ordres (Serum, OpenBook v2)
                 Ok(SwapSimulation {
                     tokens_out: 0,
                     tokens_out_min: 0,
@@ -336,6 +357,14 @@ impl SandwichEngine {
                     price_impact_bps: 0,
                 })
             }
+            DexType::PumpFun => {
+                // TODO: Implémenter le calcul pour la bonding curve Pump.fun
+                Ok(SwapSimulation {
+                    tokens_out: 0,
+                    tokens_out_min: 0,
+                    price_impact_bps: 0,
+                })
+            }
             DexType::Unsupported => {
                 // DEX non supporté - ne peut pas calculer
                 Err(anyhow!("DEX non supporté pour le calcul de profit"))
@@ -351,10 +380,40 @@ impl SandwichEngine {
         }
     }
 
-    pub async fn build_transaction_log(&self, swap: &ParsedSwap, profit: &SwapSimulation) -> Result<TransactionLog> {
+    /// Simule un swap x*y=k sur les réserves du pool (frais du pool inclus si activés)
+    fn simulate_amm_swap(&self, pool: &PoolInfo, amount_in: u64, a_to_b: bool) -> SwapSimulation {
+        let (reserve_in, reserve_out) = if a_to_b {
+            (pool.reserve_a as f64, pool.reserve_b as f64)
+        } else {
+            (pool.reserve_b as f64, pool.reserve_a as f64)
+        };
+
+        let fee_bps = if self.config.fee_adjusted_impact { pool.fee_bps } else { 0 };
+        let tokens_out = amm_out_with_fee(reserve_in, reserve_out, amount_in as f64, fee_bps);
+        if tokens_out <= 0.0 {
+            return SwapSimulation { tokens_out: 0, tokens_out_min: 0, price_impact_bps: 0 };
+        }
+
+        let tokens_out_min = tokens_out * (10_000.0 - self.config.max_slippage_bps as f64) / 10_000.0;
+
+        let price_before = reserve_out / reserve_in;
+        let price_after = (reserve_out - tokens_out) / (reserve_in + amount_in as f64);
+        let price_impact_bps = ((price_before - price_after) / price_before * 10_000.0).abs();
+
+        SwapSimulation {
+            tokens_out: tokens_out as u64,
+            tokens_out_min: tokens_out_min.max(0.0) as u64,
+            price_impact_bps: price_impact_bps as u64,
+        }
+    }
+
+    /// Décimales de token_in/token_out lues dans le cache du PoolParser qui a parsé le pool (None si inconnues)
+    pub async fn build_transaction_log(&self, swap: &ParsedSwap, profit: &SwapSimulation, pool_parser: &PoolParser) -> Result<TransactionLog> {
         let pool = &swap.pool;
         let pool_fee_bps = pool.fee_bps as u64;
-        
+        let token_in_decimals = pool_parser.cached_decimals(&swap.token_in).await;
+        let token_out_decimals = pool_parser.cached_decimals(&swap.token_out).await;
+
         Ok(TransactionLog {
             timestamp: chrono::Utc::now().to_string(),
             signature: "".to_string(), // Sera rempli après soumission
@@ -365,6 +424,8 @@ impl SandwichEngine {
             token_out: swap.token_out.to_string(),
             amount_in: swap.amount_in,
             amount_out_min: swap.amount_out_min,
+            token_in_decimals,
+            token_out_decimals,
             a_to_b: swap.a_to_b,
             pool_reserve_a: pool.reserve_a,
             pool_reserve_b: pool.reserve_b,
@@ -376,7 +437,7 @@ impl SandwichEngine {
             estimated_mcap_after: 0.0, // TODO: Calculer
             our_position_size: profit.tokens_out_min,
             estimated_profit_pct: 0.0, // TODO: Calculer
-            estimated_profit_lamports: profit.tokens_out,
+            estimated_profit_lamports: 0, // TODO: Calculer (tokens_out est en unités de token_out, pas en lamports)
             gas_cost_lamports: 0, // TODO: Calculer
             liquidity_usd: 0.0, // TODO: Calculer
             bundle_id: None,
@@ -385,32 +446,158 @@ impl SandwichEngine {
         })
     }
 
+    /// Ajoute le log au journal `transaction_log_path` (JSON Lines, ou format lisible si `transaction_log_pretty`)
     pub async fn log_transaction(&self, log: &TransactionLog) -> Result<()> {
-        let log_file = "sandwich_transactions.log";
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file)?;
+        if self.config.transaction_log_pretty {
+            self.log_transaction_pretty(log)
+        } else {
+            self.log_transaction_json(log)
+        }
+    }
+
+    /// Un objet JSON par ligne avec tous les champs de `TransactionLog`
+    pub fn log_transaction_json(&self, log: &TransactionLog) -> Result<()> {
+        let mut line = serde_json::to_vec(log)?;
+        line.push(b'\n');
+        self.open_transaction_log()?.write_all(&line)?;
+        Ok(())
+    }
 
+    /// Ligne `a | b | ...` pour suivre le journal à l'œil (sous-ensemble des champs)
+    fn log_transaction_pretty(&self, log: &TransactionLog) -> Result<()> {
         let log_line = format!(
-            "{:?} | {} | {} | {} | {} -> {} | {} -> {} | Impact: {:.2}% | Fee: {}bps | Profit: {} SOL | Gas: {} SOL | Position: {} | Success: {}\n",
+            "{:?} | {} | {} | {} | {} -> {} | In: {} | Out min: {} | Impact: {:.2}% | Fee: {}bps | Profit: {:.6} SOL | Gas: {:.6} SOL | Position: {} | Success: {}\n",
             log.timestamp,
             log.signature,
             log.dex_type,
             log.pool_id,
             log.token_in,
             log.token_out,
-            log.amount_in,
-            log.amount_out_min,
+            format_token_amount(log.amount_in, log.token_in_decimals),
+            format_token_amount(log.amount_out_min, log.token_out_decimals),
             log.price_impact_pct,
             log.pool_fee_bps,
             log.estimated_profit_lamports as f64 / 1e9,
             log.gas_cost_lamports as f64 / 1e9,
-            log.our_position_size,
+            format_token_amount(log.our_position_size, log.token_out_decimals),
             log.success
         );
 
-        file.write_all(log_line.as_bytes())?;
+        self.open_transaction_log()?.write_all(log_line.as_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn open_transaction_log(&self) -> Result<std::fs::File> {
+        let path = &self.config.transaction_log_path;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("Ouverture du journal {} impossible: {}", path.display(), e))
+    }
+}
+
+/// Montant en unités UI si les décimales sont connues, sinon brut explicitement marqué
+fn format_token_amount(raw: u64, decimals: Option<u8>) -> String {
+    match decimals {
+        Some(decimals) => format!("{:.6} (UI)", raw as f64 / 10f64.powi(decimals as i32)),
+        None => format!("{} (brut)", raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{pool_info, test_config, wsol, FakeRpc};
+    use solana_sdk::{hash::Hash, system_instruction};
+
+    fn sandwich_engine(rpc: &FakeRpc, config: BotConfig) -> SandwichEngine {
+        let config = Arc::new(config);
+        let monitoring_engine = MonitoringEngine::new(
+            Arc::clone(&config),
+            rpc.blocking_client(),
+            rpc.client(),
+            Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+            AHashMap::new(),
+            Arc::new(tokio::sync::RwLock::new(AHashMap::new())),
+        );
+        SandwichEngine::new(config, Arc::new(monitoring_engine), rpc.blocking_client(), rpc.client(), AHashMap::new(), Keypair::new())
+    }
+
+    #[tokio::test]
+    async fn test_mode_returns_dry_run_signature_without_any_rpc_call() {
+        let rpc = FakeRpc::new();
+        let mut config = test_config();
+        config.test_mode = true;
+        let engine = sandwich_engine(&rpc, config);
+
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_signed_with_payer(&[transfer], Some(&payer.pubkey()), &[&payer], Hash::default());
+        let expected = format!("DRYRUN-{}", tx.message.hash());
+
+        let signature = engine.submit_bundle_with_retry(vec![tx.clone(), tx]).await.unwrap();
+        assert_eq!(signature, expected);
+        assert_eq!(rpc.request_count(), 0);
+    }
+
+    #[test]
+    fn dry_run_bundle_transactions_decode_back_from_base64() {
+        use base64::Engine;
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_signed_with_payer(&[transfer], Some(&payer.pubkey()), &[&payer], Hash::default());
+
+        let encoded = SandwichEngine::encode_bundle_transaction(&tx).unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        let decoded: Transaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, tx);
+        assert!(decoded.verify().is_ok());
+    }
+
+    #[tokio::test]
+    async fn json_transaction_log_lines_read_back_as_transaction_logs() {
+        let rpc = FakeRpc::new();
+        let mut config = test_config();
+        config.transaction_log_path = std::env::temp_dir().join(format!("sandwich-bot-log-{}.jsonl", std::process::id()));
+        config.transaction_log_pretty = false;
+        let path = config.transaction_log_path.clone();
+        let _ = std::fs::remove_file(&path);
+        let engine = sandwich_engine(&rpc, config);
+
+        let token = Pubkey::new_unique();
+        let swap = ParsedSwap {
+            signature: "victime".to_string(),
+            user: Pubkey::new_unique(),
+            pool: pool_info(token, wsol(), 1_000_000_000_000, 500_000_000_000, 6, 9),
+            amount_in: 2_000_000_000,
+            amount_out_min: 3_900_000_000,
+            token_in: wsol(),
+            token_out: token,
+            timestamp: std::time::Instant::now(),
+            a_to_b: false,
+        };
+        let simulation = SwapSimulation { tokens_out: 4_000_000_000, tokens_out_min: 3_960_000_000, price_impact_bps: 125 };
+        let mut log = engine.build_transaction_log(&swap, &simulation, &PoolParser::new(rpc.client())).await.unwrap();
+        engine.log_transaction(&log).await.unwrap();
+        log.signature = "bundle".to_string();
+        log.success = true;
+        log.failure_reason = Some("aucune".to_string());
+        engine.log_transaction(&log).await.unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let logs: Vec<TransactionLog> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(serde_json::to_value(&logs[1]).unwrap(), serde_json::to_value(&log).unwrap());
+        assert_eq!((logs[0].signature.as_str(), logs[0].success, logs[0].failure_reason.as_deref()), ("", false, None));
+
+        let first = &logs[0];
+        assert_eq!((first.pool_id.as_str(), first.dex_type.as_str()), (swap.pool.pool_id.to_string().as_str(), "RaydiumV4"));
+        assert_eq!((first.token_in.clone(), first.token_out.clone()), (wsol().to_string(), token.to_string()));
+        assert_eq!((first.amount_in, first.amount_out_min, first.our_position_size), (2_000_000_000, 3_900_000_000, 3_960_000_000));
+        // Décimales du WSOL connues sans RPC, celles du token jamais lues : absentes plutôt que devinées
+        assert_eq!((first.token_in_decimals, first.token_out_decimals), (Some(9), None));
+        assert_eq!(first.price_impact_pct, 1.25);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::config::BotConfig;
+use anyhow::Result;
+
+// ============================================================================
+// TRACES DU PIPELINE D'ANALYSE
+// ============================================================================
+//
+// Les étapes de l'analyse (fetch_tx, extract_user, analyze_tokens, get_supply,
+// extract_pools, compute_impact) sont instrumentées avec des spans `tracing`,
+// regroupées sous un span `analyze_transaction` par signature. Sans subscriber
+// elles ne coûtent quasiment rien ; avec la feature `otel` et `otlp_endpoint`
+// renseigné, elles sont exportées en OTLP (Jaeger, Tempo...).
+
+/// Installe l'export OTLP des spans si `config.otlp_endpoint` est défini
+#[cfg(feature = "otel")]
+pub fn init_tracing(config: &BotConfig) -> Result<()> {
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{trace, Resource};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(());
+    };
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint.clone()),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", "sandwich-bot")])),
+        )
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+
+    log::info!("🔭 Traces OTLP exportées vers {}", endpoint);
+    Ok(())
+}
+
+/// Sans la feature `otel` : les spans restent locaux (aucun export)
+#[cfg(not(feature = "otel"))]
+pub fn init_tracing(config: &BotConfig) -> Result<()> {
+    if config.otlp_endpoint.is_some() {
+        log::warn!("⚠️ OTLP_ENDPOINT défini mais binaire compilé sans la feature `otel` : traces non exportées");
+    }
+    Ok(())
+}
+
+/// Vide les spans en attente avant l'arrêt du process
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
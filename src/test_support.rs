@@ -0,0 +1,405 @@
+use crate::config::{BotConfig, ConfigFile};
+use crate::types::{DexType, OrcaWhirlpoolInfo, PoolInfo, USDC_MINT, WSOL_MINT};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_account_decoder::{parse_token::UiTokenAmount, UiAccount, UiAccountEncoding};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::{ErrorKind as ClientErrorKind, Result as ClientResult};
+use solana_rpc_client_api::request::RpcRequest;
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionTokenBalance};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+// ============================================================================
+// OUTILS DE TEST
+// ============================================================================
+//
+// Configuration de test, RPC simulé (comptes en mémoire, comptage des appels), fabriques de comptes SPL,
+// de comptes de pool au layout on-chain et de PoolInfo partagées par les tests unitaires.
+
+/// Configuration par défaut, indépendante de l'environnement du process, avec un keypair éphémère
+pub fn test_config() -> BotConfig {
+    BotConfig::from_sources_with(ConfigFile::default(), test_env(&[])).expect("config de test")
+}
+
+/// Environnement simulé : `vars` plus une PRIVATE_KEY éphémère (sauf si `vars` en fournit une)
+pub fn test_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let mut vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    vars.entry("PRIVATE_KEY".to_string()).or_insert_with(|| Keypair::new().to_base58_string());
+    move |var| vars.get(var).cloned()
+}
+
+pub fn wsol() -> Pubkey {
+    Pubkey::from_str(WSOL_MINT).unwrap()
+}
+
+pub fn usdc() -> Pubkey {
+    Pubkey::from_str(USDC_MINT).unwrap()
+}
+
+/// Compte mint SPL Token
+pub fn mint_account(decimals: u8, supply: u64) -> Account {
+    let mut data = vec![0u8; Mint::LEN];
+    Mint::pack(
+        Mint { mint_authority: COption::None, supply, decimals, is_initialized: true, freeze_authority: COption::None },
+        &mut data,
+    )
+    .unwrap();
+    Account { lamports: 1_461_600, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+}
+
+/// Token account SPL (vault de pool)
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount::pack(
+        TokenAccount { mint: *mint, owner: *owner, amount, state: AccountState::Initialized, ..Default::default() },
+        &mut data,
+    )
+    .unwrap();
+    Account { lamports: 2_039_280, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+}
+
+/// Compte Whirlpool écrit octet par octet aux offsets du layout publié par Orca
+/// (whirlpools_config 8, tick_spacing 41, fee_rate 45, sqrt_price 65, token_mint_a 101, token_vault_a 133,
+/// token_mint_b 181, token_vault_b 213 ; 653 octets), indépendamment de la struct Borsh
+pub fn whirlpool_account_data(
+    mint_a: &Pubkey,
+    vault_a: &Pubkey,
+    mint_b: &Pubkey,
+    vault_b: &Pubkey,
+    sqrt_price_x64: u128,
+    tick_spacing: u16,
+    fee_rate: u16,
+) -> Vec<u8> {
+    let mut data = vec![0u8; 653];
+    let mut put = |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    put(0, &OrcaWhirlpoolInfo::DISCRIMINATOR);
+    put(8, Pubkey::new_unique().as_ref());
+    put(40, &[255]);
+    put(41, &tick_spacing.to_le_bytes());
+    put(43, &tick_spacing.to_le_bytes());
+    put(45, &fee_rate.to_le_bytes());
+    put(47, &1_300u16.to_le_bytes());
+    put(49, &1_000_000_000_000u128.to_le_bytes());
+    put(65, &sqrt_price_x64.to_le_bytes());
+    let price = (sqrt_price_x64 as f64 / 2f64.powi(64)).powi(2);
+    put(81, &((price.ln() / 1.0001f64.ln()).floor() as i32).to_le_bytes());
+    put(101, mint_a.as_ref());
+    put(133, vault_a.as_ref());
+    put(181, mint_b.as_ref());
+    put(213, vault_b.as_ref());
+    put(261, &1_700_000_000u64.to_le_bytes());
+    data
+}
+
+/// Compte AmmInfo Raydium V4 écrit aux offsets du layout publié par Raydium (status 0, base_decimal 32,
+/// swap_fee 176/184, base_vault 336, quote_vault 368, base_mint 400, quote_mint 432, lp_amount 720,
+/// client_order_id 728, padding 736 ; 752 octets), indépendamment de la struct Borsh
+pub fn raydium_v4_account_data(base_mint: &Pubkey, base_vault: &Pubkey, quote_mint: &Pubkey, quote_vault: &Pubkey, decimals: (u8, u8)) -> Vec<u8> {
+    let mut data = vec![0u8; 752];
+    let mut put = |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    put(0, &6u64.to_le_bytes());
+    put(8, &254u64.to_le_bytes());
+    put(32, &(decimals.0 as u64).to_le_bytes());
+    put(40, &(decimals.1 as u64).to_le_bytes());
+    put(144, &25u64.to_le_bytes());
+    put(152, &10_000u64.to_le_bytes());
+    put(176, &25u64.to_le_bytes());
+    put(184, &10_000u64.to_le_bytes());
+    put(336, base_vault.as_ref());
+    put(368, quote_vault.as_ref());
+    put(400, base_mint.as_ref());
+    put(432, quote_mint.as_ref());
+    for offset in (464..=688).step_by(32) {
+        put(offset, Pubkey::new_unique().as_ref());
+    }
+    put(720, &1_000_000u64.to_le_bytes());
+    put(728, &42u64.to_le_bytes());
+    data
+}
+
+/// Compte PoolState Raydium CLMM écrit aux offsets du layout publié par Raydium (amm_config 9, token_mint_0 73,
+/// token_mint_1 105, token_vault_0 137, token_vault_1 169, mint_decimals 233/234, tick_spacing 235,
+/// liquidity 237, sqrt_price_x64 253, tick_current 269 ; 1544 octets). Données synthétiques, pas un compte capturé
+pub fn raydium_clmm_account_data(
+    amm_config: &Pubkey,
+    (mint_0, vault_0, decimals_0): (&Pubkey, &Pubkey, u8),
+    (mint_1, vault_1, decimals_1): (&Pubkey, &Pubkey, u8),
+    sqrt_price_x64: u128,
+) -> Vec<u8> {
+    let mut data = vec![0u8; 1544];
+    let mut put = |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    put(0, &[247, 237, 227, 245, 215, 195, 222, 70]);
+    put(8, &[255]);
+    put(9, amm_config.as_ref());
+    put(41, Pubkey::new_unique().as_ref());
+    put(73, mint_0.as_ref());
+    put(105, mint_1.as_ref());
+    put(137, vault_0.as_ref());
+    put(169, vault_1.as_ref());
+    put(201, Pubkey::new_unique().as_ref());
+    put(233, &[decimals_0, decimals_1]);
+    put(235, &60u16.to_le_bytes());
+    put(237, &1_000_000_000_000u128.to_le_bytes());
+    put(253, &sqrt_price_x64.to_le_bytes());
+    data
+}
+
+/// Compte AmmConfig Raydium CLMM : trade_fee_rate (millionièmes) à l'offset 47
+pub fn raydium_amm_config_account(trade_fee_rate: u32) -> Account {
+    let mut data = vec![0u8; 117];
+    data[47..51].copy_from_slice(&trade_fee_rate.to_le_bytes());
+    Account { lamports: 1_705_200, data, owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+}
+
+/// Compte détenu par `owner` (compte de pool d'un programme DEX)
+pub fn program_account(owner: &Pubkey, data: Vec<u8>) -> Account {
+    Account { lamports: 6_124_800, data, owner: *owner, executable: false, rent_epoch: 0 }
+}
+
+/// Compte PriceUpdateV2 Pyth, vérification Full : discriminator (8), write_authority (32), niveau (1),
+/// puis feed_id (32), price, conf, exponent, publish_time
+pub fn pyth_price_update_account(price: i64, exponent: i32, publish_time: i64) -> Account {
+    let mut data = vec![0u8; 134];
+    data[40] = 1;
+    data[73..81].copy_from_slice(&price.to_le_bytes());
+    data[81..89].copy_from_slice(&(price.unsigned_abs() / 1_000).to_le_bytes());
+    data[89..93].copy_from_slice(&exponent.to_le_bytes());
+    data[93..101].copy_from_slice(&publish_time.to_le_bytes());
+    program_account(&Pubkey::new_unique(), data)
+}
+
+/// Balance de token d'une transaction (pre ou post), montant en unités UI
+pub fn token_balance(account_index: u8, mint: &Pubkey, owner: &str, ui_amount: f64, decimals: u8) -> UiTransactionTokenBalance {
+    let raw = (ui_amount * 10f64.powi(decimals as i32)).round() as u64;
+    UiTransactionTokenBalance {
+        account_index,
+        mint: mint.to_string(),
+        ui_token_amount: UiTokenAmount {
+            ui_amount: Some(ui_amount),
+            decimals,
+            amount: raw.to_string(),
+            ui_amount_string: ui_amount.to_string(),
+        },
+        owner: OptionSerializer::Some(owner.to_string()),
+        program_id: OptionSerializer::Some(spl_token::id().to_string()),
+    }
+}
+
+/// Transaction au format jsonParsed (réponse getTransaction) : clés de comptes avec leur flag signer,
+/// balances de tokens pre/post, aucune instruction
+pub fn parsed_transaction_json(
+    signature: &str,
+    account_keys: &[(Pubkey, bool)],
+    pre_token_balances: &[UiTransactionTokenBalance],
+    post_token_balances: &[UiTransactionTokenBalance],
+) -> Value {
+    let keys: Vec<Value> = account_keys
+        .iter()
+        .map(|(key, signer)| json!({ "pubkey": key.to_string(), "writable": true, "signer": signer, "source": "transaction" }))
+        .collect();
+    json!({
+        "slot": 1,
+        "blockTime": null,
+        "transaction": {
+            "signatures": [signature],
+            "message": { "accountKeys": keys, "recentBlockhash": Pubkey::default().to_string(), "instructions": [] },
+        },
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": pre_token_balances,
+            "postTokenBalances": post_token_balances,
+        },
+        "version": "legacy",
+    })
+}
+
+pub fn parsed_transaction(
+    account_keys: &[(Pubkey, bool)],
+    pre_token_balances: &[UiTransactionTokenBalance],
+    post_token_balances: &[UiTransactionTokenBalance],
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    let value = parsed_transaction_json("test", account_keys, pre_token_balances, post_token_balances);
+    serde_json::from_value(value).expect("transaction de test")
+}
+
+/// PoolInfo minimal (réserves brutes, liquidités UI), les autres champs à leur valeur neutre
+pub fn pool_info(token_a_mint: Pubkey, token_b_mint: Pubkey, reserve_a: u64, reserve_b: u64, decimals_a: u8, decimals_b: u8) -> PoolInfo {
+    PoolInfo {
+        dex_type: DexType::RaydiumV4,
+        program_id: Pubkey::new_unique(),
+        pool_id: Pubkey::new_unique(),
+        token_a_mint,
+        token_b_mint,
+        token_a_vault: Pubkey::new_unique(),
+        token_b_vault: Pubkey::new_unique(),
+        reserve_a,
+        reserve_b,
+        fee_bps: 25,
+        tick_spacing: None,
+        tick_current: None,
+        bin_step: None,
+        liquidity_usd: 0.0,
+        token_a_liquidity: reserve_a as f64 / 10f64.powi(decimals_a as i32),
+        token_b_liquidity: reserve_b as f64 / 10f64.powi(decimals_b as i32),
+        market_cap_usd: None,
+        token_price_usd: None,
+        total_supply: None,
+        dlmm_bins: None,
+        dlmm_fee: None,
+        decimals_assumed: false,
+        order_book: None,
+        observed_quote_in: None,
+    }
+}
+
+// ============================================================================
+// RPC SIMULÉ
+// ============================================================================
+
+#[derive(Default)]
+struct FakeRpcState {
+    accounts: Mutex<HashMap<Pubkey, Account>>,
+    transactions: Mutex<HashMap<String, Value>>,
+    requests: Mutex<Vec<String>>,
+}
+
+/// Nœud RPC en mémoire : getVersion, getAccountInfo, getMultipleAccounts, getProgramAccounts (filtres appliqués)
+/// et getTokenSupply répondent depuis les comptes enregistrés, getTransaction depuis les transactions enregistrées (null sinon),
+/// toute autre méthode est une erreur. Les appels sont comptés par méthode.
+#[derive(Clone, Default)]
+pub struct FakeRpc {
+    state: Arc<FakeRpcState>,
+}
+
+impl FakeRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_account(&self, key: Pubkey, account: Account) {
+        self.state.accounts.lock().unwrap().insert(key, account);
+    }
+
+    /// Réponse getTransaction (JSON, cf. `parsed_transaction_json`) pour `signature`
+    pub fn add_transaction(&self, signature: &str, transaction: Value) {
+        self.state.transactions.lock().unwrap().insert(signature.to_string(), transaction);
+    }
+
+    pub fn client(&self) -> Arc<AsyncRpcClient> {
+        Arc::new(AsyncRpcClient::new_sender(
+            FakeSender { state: Arc::clone(&self.state) },
+            RpcClientConfig::with_commitment(CommitmentConfig::processed()),
+        ))
+    }
+
+    /// Client bloquant sur le même nœud simulé (envoi de transactions)
+    pub fn blocking_client(&self) -> Arc<RpcClient> {
+        Arc::new(RpcClient::new_sender(
+            FakeSender { state: Arc::clone(&self.state) },
+            RpcClientConfig::with_commitment(CommitmentConfig::processed()),
+        ))
+    }
+
+    /// Nombre total d'appels, toutes méthodes confondues
+    pub fn request_count(&self) -> usize {
+        self.state.requests.lock().unwrap().len()
+    }
+
+    /// Nombre d'appels d'une méthode donnée
+    pub fn count(&self, method: &str) -> usize {
+        self.state.requests.lock().unwrap().iter().filter(|m| *m == method).count()
+    }
+}
+
+struct FakeSender {
+    state: Arc<FakeRpcState>,
+}
+
+impl FakeSender {
+    fn encoded_account(&self, key: &Value) -> Option<UiAccount> {
+        let key = Pubkey::from_str(key.as_str()?).ok()?;
+        let account = self.state.accounts.lock().unwrap().get(&key)?.clone();
+        Some(UiAccount::encode(&key, &AccountSharedData::from(account), UiAccountEncoding::Base64, None, None))
+    }
+}
+
+fn with_context(value: Value) -> Value {
+    json!({ "context": { "slot": 1 }, "value": value })
+}
+
+#[async_trait]
+impl RpcSender for FakeSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let method = request.to_string();
+        self.state.requests.lock().unwrap().push(method.clone());
+
+        match method.as_str() {
+            "getVersion" => Ok(json!({ "solana-core": "1.18.26", "feature-set": 0 })),
+            "getAccountInfo" => Ok(with_context(json!(self.encoded_account(&params[0])))),
+            "getMultipleAccounts" => {
+                let keys = params[0].as_array().cloned().unwrap_or_default();
+                let accounts: Vec<_> = keys.iter().map(|key| self.encoded_account(key)).collect();
+                Ok(with_context(json!(accounts)))
+            }
+            "getProgramAccounts" => {
+                let program = Pubkey::from_str(params[0].as_str().unwrap_or_default()).unwrap_or_default();
+                let filters: Vec<RpcFilterType> = serde_json::from_value(params[1]["filters"].clone()).unwrap_or_default();
+                let accounts = self.state.accounts.lock().unwrap().clone();
+                let keyed: Vec<Value> = accounts
+                    .into_iter()
+                    .filter(|(_, account)| account.owner == program)
+                    .map(|(key, account)| (key, AccountSharedData::from(account)))
+                    .filter(|(_, account)| filters.iter().all(|filter| filter.allows(account)))
+                    .map(|(key, account)| {
+                        let account = UiAccount::encode(&key, &account, UiAccountEncoding::Base64, None, None);
+                        json!({ "pubkey": key.to_string(), "account": account })
+                    })
+                    .collect();
+                Ok(json!(keyed))
+            }
+            "getTokenSupply" => {
+                let key = Pubkey::from_str(params[0].as_str().unwrap_or_default()).unwrap_or_default();
+                let account = self.state.accounts.lock().unwrap().get(&key).cloned()
+                    .ok_or_else(|| ClientErrorKind::Custom(format!("mint inconnu: {}", key)))?;
+                let mint = Mint::unpack(&account.data).map_err(|e| ClientErrorKind::Custom(e.to_string()))?;
+                let ui_amount = mint.supply as f64 / 10f64.powi(mint.decimals as i32);
+                Ok(with_context(json!(UiTokenAmount {
+                    ui_amount: Some(ui_amount),
+                    decimals: mint.decimals,
+                    amount: mint.supply.to_string(),
+                    ui_amount_string: ui_amount.to_string(),
+                })))
+            }
+            "getTransaction" => {
+                let signature = params[0].as_str().unwrap_or_default();
+                Ok(self.state.transactions.lock().unwrap().get(signature).cloned().unwrap_or(Value::Null))
+            }
+            _ => Err(ClientErrorKind::Custom(format!("méthode RPC non simulée: {}", method)).into()),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "fake".to_string()
+    }
+}
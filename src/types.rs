@@ -1,6 +1,7 @@
 use solana_sdk::pubkey::Pubkey;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Serialize, Deserialize};
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 
 // ============================================================================
@@ -11,6 +12,19 @@ pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCwuBvf9Sg8ePdLA";
 pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
 pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
 pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+pub const PUMPFUN_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+// Tous les tokens Pump.fun sont créés avec 6 décimales
+pub const PUMPFUN_TOKEN_DECIMALS: u8 = 6;
+pub const PUMPFUN_FEE_BPS: u16 = 100;
+pub const OPENBOOK_V2_PROGRAM: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+pub const RAYDIUM_V4_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const RAYDIUM_CLMM_PROGRAM: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUQpFuLUUamH4uV8K9";
+pub const ORCA_WHIRLPOOL_PROGRAM: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+pub const METEORA_DLMM_PROGRAM: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+// Frais du tier le plus courant, si le compte AmmConfig n'est pas lisible
+pub const RAYDIUM_CLMM_DEFAULT_FEE_BPS: u16 = 25;
+// Meteora DLMM : 70 bins par compte BinArray
+pub const METEORA_BINS_PER_ARRAY: i32 = 70;
 
 
 // Jito tip accounts
@@ -24,15 +38,18 @@ pub const JITO_TIP_ACCOUNTS: &[&str] = &[
 // STRUCTURES
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub enum DexType {
     RaydiumV4,
+    RaydiumCLMM,  // Liquidité concentrée (prix via sqrt_price)
     OrcaWhirlpool,
     MeteoraDLMM,
     Lifinity,
     Phoenix,
     Serum,
+    OpenBookV2,   // Carnet d'ordres (successeur de Serum)
     Jupiter,
+    PumpFun,      // Bonding curve, pas un AMM classique
     Unsupported,  // DEX connu mais non supporté
     Unknown,      // DEX complètement inconnu
 }
@@ -60,6 +77,44 @@ pub struct PoolInfo {
     pub market_cap_usd: Option<f64>,
     pub token_price_usd: Option<f64>,
     pub total_supply: Option<u64>,
+    // Meteora DLMM : liquidité réelle des bins autour du bin actif (si bin arrays récupérés)
+    pub dlmm_bins: Option<Vec<DlmmBin>>,
+    // Pools à frais dynamiques : fee_bps est le frais de base, le frais effectif dépend de la taille du swap
+    pub dlmm_fee: Option<DlmmFeeParameters>,
+    // Décimales d'un des mints supposées (fetch échoué) : liquidité, prix et mcap moins fiables
+    pub decimals_assumed: bool,
+    // Carnets d'ordres : meilleurs prix et profondeur (les réserves sont les dépôts du market)
+    pub order_book: Option<OrderBookSummary>,
+    // Pools extraites d'une transaction : quote effectivement entrée dans le pool (unités UI), si achat
+    pub observed_quote_in: Option<f64>,
+}
+
+/// Sommet d'un carnet d'ordres, en unités UI (prix en token de quote par token de base)
+#[derive(Debug, Clone, Default)]
+pub struct OrderBookSummary {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub bid_depth_base: f64,
+    pub ask_depth_base: f64,
+}
+
+impl OrderBookSummary {
+    /// Prix médian, ou le seul côté disponible
+    pub fn mid_price(&self) -> Option<f64> {
+        match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            (bid, ask) => bid.or(ask),
+        }
+    }
+}
+
+/// Raison pour laquelle un pool est écarté par le filtre de validité
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolSkipReason {
+    LiquidityOutOfRange { liquidity_usd: f64 },
+    EmptyReserves,
+    McapOutOfRange { mcap_usd: f64 },
+    ReserveImbalance { ratio: f64 },
 }
 
 #[derive(Debug, Clone)]
@@ -104,6 +159,57 @@ pub struct ParsedSwapInstruction {
     pub a_to_b: bool,
 }
 
+/// Source du prix SOL, essayées dans l'ordre de `BotConfig::sol_price_sources`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolPriceSource {
+    CoinGecko,
+    Pyth,        // Compte PriceUpdateV2 SOL/USD du Pyth Solana Receiver
+    OnChainPool, // Ratio des réserves d'une pool WSOL/USDC Raydium V4
+}
+
+/// Champs utiles d'un compte PriceUpdateV2 Pyth (pull oracle)
+#[derive(Debug)]
+pub struct PythPriceUpdate {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+impl PythPriceUpdate {
+    /// Décode le compte : discriminator (8), write_authority (32), verification_level
+    /// (enum Borsh : Partial { num_signatures: u8 } sur 2 octets, Full sur 1), puis le price message
+    /// (feed_id sur 32 octets, price, conf, exponent, publish_time)
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        let message = match data.get(40) {
+            Some(0) => 42,
+            Some(1) => 41,
+            Some(tag) => return Err(anyhow::anyhow!("Niveau de vérification Pyth inconnu: {}", tag)),
+            None => return Err(anyhow::anyhow!("Compte Pyth trop court: {} octets", data.len())),
+        };
+        let fields = message + 32;
+        let bytes = |o: usize, n: usize| data.get(fields + o..fields + o + n)
+            .ok_or_else(|| anyhow::anyhow!("Compte Pyth trop court: {} octets", data.len()));
+        Ok(Self {
+            price: i64::from_le_bytes(bytes(0, 8)?.try_into()?),
+            conf: u64::from_le_bytes(bytes(8, 8)?.try_into()?),
+            exponent: i32::from_le_bytes(bytes(16, 4)?.try_into()?),
+            publish_time: i64::from_le_bytes(bytes(20, 8)?.try_into()?),
+        })
+    }
+
+    pub fn price_f64(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.exponent)
+    }
+}
+
+/// Dernier prix SOL valide, persisté sur disque pour redémarrer sans attendre l'API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedSolPrice {
+    pub price: f64,
+    pub updated_at: i64, // timestamp unix (secondes)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BundleStatus {
     pub bundle_id: String,
@@ -111,6 +217,8 @@ pub struct BundleStatus {
     pub landed_slot: Option<u64>,
 }
 
+/// Unités : les montants `u64` sont bruts (unités de base du mint, ou lamports quand le nom l'indique),
+/// les `*_decimals` permettent de les convertir en unités UI ; prix et mcap en USD.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionLog {
     pub timestamp: String,
@@ -122,31 +230,33 @@ pub struct TransactionLog {
     pub user: String,
     pub token_in: String,
     pub token_out: String,
-    pub amount_in: u64,
-    pub amount_out_min: u64,
+    pub amount_in: u64,       // brut, décimales de token_in
+    pub amount_out_min: u64,  // brut, décimales de token_out
+    pub token_in_decimals: Option<u8>,  // None : inconnues (montants affichés bruts)
+    pub token_out_decimals: Option<u8>,
     pub a_to_b: bool,
     
     // Informations sur le pool
-    pub pool_reserve_a: u64,
-    pub pool_reserve_b: u64,
+    pub pool_reserve_a: u64, // brut, décimales du token A du pool
+    pub pool_reserve_b: u64, // brut, décimales du token B du pool
     pub pool_fee_bps: u64,
     
-    // Estimation du prix
+    // Estimation du prix (USD)
     pub price_before: f64,
     pub price_after: f64,
     pub price_impact_pct: f64,
     
-    // Capitalisation estimée
+    // Capitalisation estimée (USD)
     pub estimated_mcap_before: f64,
     pub estimated_mcap_after: f64,
     
     // Analyse de rentabilité
-    pub our_position_size: u64,
+    pub our_position_size: u64, // brut, décimales de token_out
     pub estimated_profit_pct: f64,
     pub estimated_profit_lamports: u64,
     pub gas_cost_lamports: u64,
     
-    // Liquidity
+    // Liquidity (USD)
     pub liquidity_usd: f64,
     
     // Status
@@ -211,16 +321,79 @@ pub struct RaydiumAmmInfo {
     pub token_temp_lp: Pubkey,
     pub amm_owner: Pubkey,
     pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub padding: [u64; 2],
+}
+
+impl RaydiumAmmInfo {
+    // Taille du compte (= taille Borsh de la struct) et offsets des mints (filtres memcmp de getProgramAccounts)
+    pub const LEN: usize = 752;
+    pub const BASE_MINT_OFFSET: usize = 400;
+    pub const QUOTE_MINT_OFFSET: usize = 432;
+}
+
+// ============================================================================
+// RAYDIUM CLMM STRUCTURES
+// ============================================================================
+
+/// Début du compte PoolState Raydium CLMM (zero-copy packé, discriminator Anchor de 8 octets) :
+/// seuls les champs jusqu'à tick_current sont décodés, le reste (observations, rewards...) est ignoré
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct RaydiumClmmInfo {
+    pub bump: u8,
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl RaydiumClmmInfo {
+    // Discriminator + champs décodés (le compte complet fait 1544 octets)
+    pub const MIN_LEN: usize = 273;
+    pub const ACCOUNT_LEN: usize = 1544;
+    // Offsets on-chain (discriminator inclus) pour les filtres memcmp
+    pub const MINT_0_OFFSET: usize = 73;
+    pub const MINT_1_OFFSET: usize = 105;
+    // AmmConfig : trade_fee_rate (u32, en millionièmes) après discriminator, bump, index, owner, protocol_fee_rate
+    const AMM_CONFIG_TRADE_FEE_OFFSET: usize = 47;
+
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        let mut body = data.get(8..Self::MIN_LEN)
+            .ok_or_else(|| anyhow::anyhow!("Compte PoolState Raydium CLMM trop court: {} octets", data.len()))?;
+        Self::deserialize(&mut body)
+            .map_err(|e| anyhow::anyhow!("Erreur parsing Raydium CLMM: {}", e))
+    }
+
+    /// Frais de swap (bps) lus dans le compte AmmConfig du pool
+    pub fn trade_fee_bps(amm_config_data: &[u8]) -> Option<u16> {
+        let offset = Self::AMM_CONFIG_TRADE_FEE_OFFSET;
+        let rate = u32::from_le_bytes(amm_config_data.get(offset..offset + 4)?.try_into().ok()?);
+        Some((rate / 100) as u16)
+    }
 }
 
 // ============================================================================
 // ORCA WHIRLPOOL STRUCTURES
 // ============================================================================
+/// Compte Whirlpool on-chain complet (discriminator Anchor inclus). Les montants des vaults ne sont
+/// pas dans le compte : les réserves se lisent dans les token accounts token_vault_a / token_vault_b.
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct OrcaWhirlpoolInfo {
-    pub start_tick_index: i32,
+    pub discriminator: [u8; 8],
+    pub whirlpools_config: Pubkey,
+    pub whirlpool_bump: [u8; 1],
     pub tick_spacing: u16,
-    pub fee_rate: u16,
+    pub fee_tier_index_seed: [u8; 2],
+    pub fee_rate: u16, // En centièmes de bp (3000 = 0,3 %)
     pub protocol_fee_rate: u16,
     pub liquidity: u128,
     pub sqrt_price: u128,
@@ -235,8 +408,31 @@ pub struct OrcaWhirlpoolInfo {
     pub fee_growth_global_b: u128,
     pub reward_last_updated_timestamp: u64,
     pub reward_infos: [OrcaRewardInfo; 3],
-    pub token_vault_a_amount: u64,
-    pub token_vault_b_amount: u64,
+}
+
+impl OrcaWhirlpoolInfo {
+    pub const DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+    // Taille du compte (= taille Borsh de la struct) et offsets des mints (filtres memcmp)
+    pub const ACCOUNT_LEN: usize = 653;
+    pub const MINT_A_OFFSET: usize = 101;
+    pub const MINT_B_OFFSET: usize = 181;
+
+    /// Décode le compte après vérification du discriminator (un autre compte du programme,
+    /// ex. TickArray ou Position, serait sinon lu comme une whirlpool)
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        let mut body = data.get(..Self::ACCOUNT_LEN)
+            .ok_or_else(|| anyhow::anyhow!("Compte Whirlpool trop court: {} octets", data.len()))?;
+        if body[..8] != Self::DISCRIMINATOR {
+            return Err(anyhow::anyhow!("Compte Orca qui n'est pas une Whirlpool (discriminator {:?})", &body[..8]));
+        }
+        Self::deserialize(&mut body)
+            .map_err(|e| anyhow::anyhow!("Erreur parsing Orca Whirlpool: {}", e))
+    }
+
+    /// Frais de swap en bps
+    pub fn fee_bps(&self) -> u16 {
+        self.fee_rate / 100
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
@@ -251,19 +447,231 @@ pub struct OrcaRewardInfo {
 // ============================================================================
 // METEORA DLMM STRUCTURES
 // ============================================================================
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
+#[derive(Debug)]
 pub struct MeteoraDLMMInfo {
     pub bin_step: u16,
     pub active_id: i32,
-    pub protocol_fee_bps: u16,
-    pub base_fee_bps: u16,
     pub reserve_x: Pubkey,
     pub reserve_y: Pubkey,
     pub mint_x: Pubkey,
     pub mint_y: Pubkey,
-    pub oracle_id: i32,
-    pub liquidity: u128,
-    pub bin_liquidity: u128,
+    pub fee_parameters: DlmmFeeParameters,
+}
+
+impl MeteoraDLMMInfo {
+    // Taille minimale lue du compte LbPair (jusqu'à reserve_y inclus)
+    const MIN_LEN: usize = 216;
+    pub const ACCOUNT_LEN: usize = 904;
+    pub const MINT_X_OFFSET: usize = 88;
+    pub const MINT_Y_OFFSET: usize = 120;
+
+    /// Décode le compte LbPair (discriminator Anchor de 8 octets, offsets du layout on-chain)
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < Self::MIN_LEN {
+            return Err(anyhow::anyhow!("Compte LbPair trop court: {} octets", data.len()));
+        }
+        let u16_at = |o: usize| u16::from_le_bytes(data[o..o + 2].try_into().expect("2 octets"));
+        let u32_at = |o: usize| u32::from_le_bytes(data[o..o + 4].try_into().expect("4 octets"));
+        let i32_at = |o: usize| i32::from_le_bytes(data[o..o + 4].try_into().expect("4 octets"));
+        let i64_at = |o: usize| i64::from_le_bytes(data[o..o + 8].try_into().expect("8 octets"));
+        let key_at = |o: usize| Pubkey::try_from(&data[o..o + 32]).expect("32 octets");
+
+        Ok(Self {
+            active_id: i32_at(76),
+            bin_step: u16_at(80),
+            mint_x: key_at(Self::MINT_X_OFFSET),
+            mint_y: key_at(Self::MINT_Y_OFFSET),
+            reserve_x: key_at(152),
+            reserve_y: key_at(184),
+            fee_parameters: DlmmFeeParameters {
+                // StaticParameters
+                base_factor: u16_at(8),
+                filter_period: u16_at(10),
+                decay_period: u16_at(12),
+                reduction_factor: u16_at(14),
+                variable_fee_control: u32_at(16),
+                max_volatility_accumulator: u32_at(20),
+                base_fee_power_factor: data[34],
+                // VariableParameters
+                volatility_accumulator: u32_at(40),
+                volatility_reference: u32_at(44),
+                index_reference: i32_at(48),
+                last_update_timestamp: i64_at(56),
+            },
+        })
+    }
+}
+
+/// Paramètres de frais dynamiques DLMM : frais de base + frais variables croissant avec
+/// la volatilité (nombre de bins traversés depuis le bin de référence)
+#[derive(Debug, Clone)]
+pub struct DlmmFeeParameters {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub base_fee_power_factor: u8,
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+}
+
+impl DlmmFeeParameters {
+    // Précision des taux de frais du programme (1e9 = 100%) et plafond (10%)
+    const FEE_PRECISION: f64 = 1_000_000_000.0;
+    const MAX_FEE_RATE: u128 = 100_000_000;
+    const BASIS_POINT_MAX: u32 = 10_000;
+
+    fn base_fee_rate(&self, bin_step: u16) -> u128 {
+        self.base_factor as u128 * bin_step as u128 * 10 * 10u128.pow(self.base_fee_power_factor as u32)
+    }
+
+    fn variable_fee_rate(&self, bin_step: u16, volatility_accumulator: u32) -> u128 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+        let v = volatility_accumulator as u128 * bin_step as u128;
+        (v * v * self.variable_fee_control as u128).div_ceil(100_000_000_000)
+    }
+
+    fn rate_to_bps(rate: u128) -> f64 {
+        rate.min(Self::MAX_FEE_RATE) as f64 / Self::FEE_PRECISION * 10_000.0
+    }
+
+    /// Frais de base (sans composante de volatilité), en bps
+    pub fn base_fee_bps(&self, bin_step: u16) -> f64 {
+        Self::rate_to_bps(self.base_fee_rate(bin_step))
+    }
+
+    /// Références de volatilité telles que le programme les recalcule au début d'un swap à `now`
+    fn references_at(&self, active_id: i32, now: i64) -> (i32, u32) {
+        let elapsed = now.saturating_sub(self.last_update_timestamp);
+        if elapsed < self.filter_period as i64 {
+            (self.index_reference, self.volatility_reference)
+        } else if elapsed < self.decay_period as i64 {
+            let decayed = self.volatility_accumulator as u64 * self.reduction_factor as u64 / Self::BASIS_POINT_MAX as u64;
+            (active_id, decayed as u32)
+        } else {
+            (active_id, 0)
+        }
+    }
+
+    /// Frais effectifs moyens (bps) d'un swap qui part de `active_id` et termine au bin `end_bin`
+    pub fn effective_fee_bps(&self, bin_step: u16, active_id: i32, end_bin: i32, now: i64) -> f64 {
+        let (index_reference, volatility_reference) = self.references_at(active_id, now);
+        let (low, high) = (active_id.min(end_bin), active_id.max(end_bin));
+        let base = self.base_fee_rate(bin_step);
+
+        let total: f64 = (low..=high)
+            .map(|bin_id| {
+                let crossed = index_reference.abs_diff(bin_id);
+                let volatility = (volatility_reference as u64 + crossed as u64 * Self::BASIS_POINT_MAX as u64)
+                    .min(self.max_volatility_accumulator as u64) as u32;
+                Self::rate_to_bps(base + self.variable_fee_rate(bin_step, volatility))
+            })
+            .sum();
+        total / (high - low + 1) as f64
+    }
+}
+
+/// Bin DLMM : réserves brutes de X et Y disponibles au prix du bin
+#[derive(Debug, Clone)]
+pub struct DlmmBin {
+    pub id: i32,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+/// Compte BinArray Meteora DLMM (discriminator Anchor de 8 octets)
+pub struct MeteoraBinArray {
+    pub index: i64,
+    pub lb_pair: Pubkey,
+    pub bins: Vec<DlmmBin>,
+}
+
+impl MeteoraBinArray {
+    // discriminator(8) + index(8) + version(1) + padding(7) + lb_pair(32)
+    const HEADER_LEN: usize = 56;
+    // amount_x, amount_y (u64) + price, liquidity_supply, reward_per_token_stored[2],
+    // fee_amount_x/y_per_token_stored, amount_x/y_in (u128)
+    const BIN_LEN: usize = 144;
+
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        let expected = Self::HEADER_LEN + Self::BIN_LEN * METEORA_BINS_PER_ARRAY as usize;
+        if data.len() < expected {
+            return Err(anyhow::anyhow!("Compte BinArray trop court: {} octets (attendu {})", data.len(), expected));
+        }
+
+        let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().expect("8 octets"));
+        let index = read_u64(8) as i64;
+        let lb_pair = Pubkey::try_from(&data[24..56])
+            .map_err(|e| anyhow::anyhow!("lb_pair invalide: {}", e))?;
+
+        let first_bin_id = index * METEORA_BINS_PER_ARRAY as i64;
+        let bins = (0..METEORA_BINS_PER_ARRAY as usize)
+            .map(|i| {
+                let offset = Self::HEADER_LEN + i * Self::BIN_LEN;
+                DlmmBin {
+                    id: (first_bin_id + i as i64) as i32,
+                    amount_x: read_u64(offset),
+                    amount_y: read_u64(offset + 8),
+                }
+            })
+            .collect();
+
+        Ok(Self { index, lb_pair, bins })
+    }
+
+    /// Index du BinArray contenant un bin (division arrondie vers -inf)
+    pub fn index_for_bin(bin_id: i32) -> i64 {
+        (bin_id as i64).div_euclid(METEORA_BINS_PER_ARRAY as i64)
+    }
+
+    /// Adresse du BinArray `index` d'une paire
+    pub fn address(program_id: &Pubkey, lb_pair: &Pubkey, index: i64) -> Pubkey {
+        Pubkey::find_program_address(&[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()], program_id).0
+    }
+}
+
+// ============================================================================
+// PUMP.FUN STRUCTURES
+// ============================================================================
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct PumpFunBondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl PumpFunBondingCurve {
+    /// Décode le compte bonding curve (discriminator Anchor de 8 octets, champs additionnels ignorés)
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        let mut body = data.get(8..)
+            .ok_or_else(|| anyhow::anyhow!("Compte bonding curve trop court: {} octets", data.len()))?;
+        Self::deserialize(&mut body)
+            .map_err(|e| anyhow::anyhow!("Erreur parsing bonding curve Pump.fun: {}", e))
+    }
+
+    /// Adresse du compte bonding curve d'un mint
+    pub fn address_for_mint(mint: &Pubkey) -> Pubkey {
+        let program_id = Pubkey::from_str(PUMPFUN_PROGRAM).expect("PUMPFUN_PROGRAM valide");
+        Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program_id).0
+    }
+
+    /// Prix d'un token en SOL (unités UI). La courbe est un x*y=k sur les réserves virtuelles.
+    pub fn price_in_sol(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        (self.virtual_sol_reserves as f64 / 1e9)
+            / (self.virtual_token_reserves as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS as i32))
+    }
 }
 
 // ============================================================================
@@ -279,6 +687,10 @@ pub struct LifinityPoolInfo {
     pub oracle: Pubkey,
 }
 
+impl LifinityPoolInfo {
+    pub const LEN: usize = 162;
+}
+
 // ============================================================================
 // PHOENIX STRUCTURES
 // ============================================================================
@@ -294,6 +706,134 @@ pub struct PhoenixMarketInfo {
     pub taker_fee_bps: u16,
 }
 
+impl PhoenixMarketInfo {
+    pub const LEN: usize = 154;
+}
+
+// ============================================================================
+// OPENBOOK V2 STRUCTURES
+// ============================================================================
+
+/// Champs utiles du compte Market OpenBook v2 (zero-copy, discriminator Anchor de 8 octets)
+#[derive(Debug)]
+pub struct OpenBookV2Market {
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub quote_lot_size: i64,
+    pub base_lot_size: i64,
+    pub taker_fee: i64, // en millionièmes
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub market_base_vault: Pubkey,
+    pub base_deposit_total: u64,
+    pub market_quote_vault: Pubkey,
+    pub quote_deposit_total: u64,
+}
+
+impl OpenBookV2Market {
+    const LEN: usize = 848;
+
+    pub fn from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < Self::LEN {
+            return Err(anyhow::anyhow!("Compte market OpenBook v2 trop court: {} octets", data.len()));
+        }
+        let i64_at = |o: usize| i64::from_le_bytes(data[o..o + 8].try_into().expect("8 octets"));
+        let key_at = |o: usize| Pubkey::try_from(&data[o..o + 32]).expect("32 octets");
+
+        Ok(Self {
+            base_decimals: data[9],
+            quote_decimals: data[10],
+            bids: key_at(200),
+            asks: key_at(232),
+            quote_lot_size: i64_at(448),
+            base_lot_size: i64_at(456),
+            taker_fee: i64_at(488),
+            base_mint: key_at(576),
+            quote_mint: key_at(608),
+            market_base_vault: key_at(640),
+            base_deposit_total: i64_at(672) as u64,
+            market_quote_vault: key_at(680),
+            quote_deposit_total: i64_at(712) as u64,
+        })
+    }
+
+    /// Frais taker en bps
+    pub fn taker_fee_bps(&self) -> u16 {
+        (self.taker_fee.max(0) / 100) as u16
+    }
+
+    /// Prix (unités UI, quote par base) d'un prix exprimé en lots
+    pub fn price_from_lots(&self, price_lots: i64) -> f64 {
+        price_lots as f64 * self.quote_lot_size as f64 / self.base_lot_size as f64
+            * 10f64.powi(self.base_decimals as i32 - self.quote_decimals as i32)
+    }
+
+    /// Meilleur prix et profondeur (unités UI de base) d'un côté du carnet
+    pub fn summarize_side(&self, orders: &[(i64, i64)], is_bids: bool) -> (Option<f64>, f64) {
+        let best_lots = if is_bids {
+            orders.iter().map(|(price, _)| *price).max()
+        } else {
+            orders.iter().map(|(price, _)| *price).min()
+        };
+        let depth_lots: i64 = orders.iter().map(|(_, quantity)| *quantity).sum();
+        (
+            best_lots.map(|lots| self.price_from_lots(lots)),
+            depth_lots as f64 * self.base_lot_size as f64 / 10f64.powi(self.base_decimals as i32),
+        )
+    }
+}
+
+/// Compte BookSide OpenBook v2 (bids ou asks) : arbre critbit de 1024 noeuds de 88 octets
+pub struct OpenBookV2BookSide;
+
+impl OpenBookV2BookSide {
+    const NODES_OFFSET: usize = 840;
+    const NODE_LEN: usize = 88;
+    const MAX_NODES: usize = 1024;
+    const TAG_INNER: u8 = 1;
+    const TAG_LEAF: u8 = 2;
+
+    /// Ordres à prix fixe (root 0, les ordres indexés sur l'oracle sont ignorés) : (prix en lots, quantité en lots)
+    pub fn fixed_orders(data: &[u8]) -> anyhow::Result<Vec<(i64, i64)>> {
+        if data.len() < Self::NODES_OFFSET + Self::NODE_LEN * Self::MAX_NODES {
+            return Err(anyhow::anyhow!("Compte BookSide OpenBook v2 trop court: {} octets", data.len()));
+        }
+        let u32_at = |o: usize| u32::from_le_bytes(data[o..o + 4].try_into().expect("4 octets"));
+        let root = u32_at(8);
+        let leaf_count = u32_at(12);
+
+        let mut orders = Vec::with_capacity(leaf_count as usize);
+        if leaf_count == 0 {
+            return Ok(orders);
+        }
+        let mut stack = vec![root];
+        // Borne de sécurité contre un arbre corrompu (cycle)
+        let mut visited = 0;
+        while let Some(index) = stack.pop() {
+            visited += 1;
+            if index as usize >= Self::MAX_NODES || visited > Self::MAX_NODES {
+                return Err(anyhow::anyhow!("Arbre BookSide OpenBook v2 invalide"));
+            }
+            let node = Self::NODES_OFFSET + index as usize * Self::NODE_LEN;
+            match data[node] {
+                Self::TAG_INNER => {
+                    stack.push(u32_at(node + 24));
+                    stack.push(u32_at(node + 28));
+                }
+                Self::TAG_LEAF => {
+                    let key = u128::from_le_bytes(data[node + 8..node + 24].try_into().expect("16 octets"));
+                    let quantity = i64::from_le_bytes(data[node + 56..node + 64].try_into().expect("8 octets"));
+                    orders.push(((key >> 64) as i64, quantity));
+                }
+                tag => return Err(anyhow::anyhow!("Noeud BookSide OpenBook v2 inattendu (tag {})", tag)),
+            }
+        }
+        Ok(orders)
+    }
+}
+
 // ============================================================================
 // SERUM STRUCTURES
 // ============================================================================
@@ -308,6 +848,10 @@ pub struct SerumMarketInfo {
     pub vault_signer_nonce: u64,
 }
 
+impl SerumMarketInfo {
+    pub const LEN: usize = 152;
+}
+
 #[derive(Debug, Clone)]
 pub struct SandwichAnalysisResult {
     pub signature: String,
@@ -319,4 +863,285 @@ pub struct SandwichAnalysisResult {
     pub execution_time: Duration,
     pub is_sandwich_opportunity: bool,
     pub estimated_profit: f64,
+    pub classification: TransactionClassification,
+    /// tokens_received obtenu par le fallback (ATAs du signataire) : confiance réduite
+    pub tokens_received_estimated: bool,
+    // Fee payer et swapper (None si l'analyse s'arrête avant de les identifier)
+    pub parties: Option<TransactionParties>,
+    // Valeurs intermédiaires du calcul d'impact (si verbose_impact_detail)
+    pub debug_detail: Option<ImpactDebug>,
+}
+
+/// Entrées et résultats intermédiaires du calcul d'impact MCap, pour auditer un impact suspect.
+/// Réserves et montants en unités UI, prix en token de quote par token.
+#[derive(Debug, Clone)]
+pub struct ImpactDebug {
+    pub pool_dex: DexType,
+    pub quote_mint: Pubkey,
+    pub reserve_token_before: f64,
+    pub reserve_quote_before: f64,
+    pub reserve_token_after: f64,
+    pub reserve_quote_after: f64,
+    pub price_before: f64,
+    pub price_after: f64,
+    pub tokens_delta: f64,
+    pub quote_in: f64,
+    pub quote_in_observed: bool, // quote lue dans les balances du pool plutôt que déduite des tokens reçus
+    pub fee_bps: u16,
+    pub sol_price: f64,
+    pub circulating_supply: f64,
+}
+
+/// Parties d'une transaction : différentes quand un relayer paie les frais à la place du swapper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionParties {
+    pub fee_payer: String,
+    pub swapper: String,
+}
+
+/// Sens du swap de la cible, vu depuis le token non-système
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapDirection {
+    /// Quote (WSOL/USDC) → token : le solde du token augmente
+    Buy,
+    /// Token → quote : le solde du token diminue
+    Sell,
+}
+
+/// Nature de la transaction analysée
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionClassification {
+    /// Swap simple : l'utilisateur reçoit réellement un token
+    Swap,
+    /// Achat et revente du même token dans la même tx (arbitrage, bundle MEV) :
+    /// variation nette ~nulle malgré un flux brut important
+    RoundTrip,
+    /// Tokens reçus disproportionnés par rapport à la réserve du pool retenu :
+    /// pool vidée ou (plus souvent) erreur de détection, impact non fiable
+    ImplausibleSize,
+    /// Impact MCap au-dessus de `OpportunityFilter::max_mcap_impact_pct` : presque toujours
+    /// une erreur de parsing ou un token invendable
+    ImplausibleImpact,
+}
+
+// ============================================================================
+// ERREURS D'ANALYSE
+// ============================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnalysisError {
+    #[error("⏰ Budget RPC épuisé pour l'analyse ({budget_ms}ms)")]
+    RpcTimeout { budget_ms: u64 },
+    #[error("Transaction émise par notre propre wallet")]
+    OwnTransaction,
+    #[error("🔁 Aller-retour/arbitrage sur {mint} (flux brut {gross_flow:.2})")]
+    RoundTrip { mint: String, gross_flow: f64 },
+    #[error("Aucun token non-système reçu détecté")]
+    NoNonSystemToken,
+    #[error("Vente de {mint} (token → quote) : aucun achat à analyser")]
+    SellSwap { mint: String },
+    #[error("Aucune pool DEX détectée dans la transaction - Transaction non analysable")]
+    NoDexPool,
+    #[error("Pool mal parsée: {reason}")]
+    PoolParseFailed { reason: String },
+    #[error("Prix SOL non disponible - attente du cache")]
+    SolPriceUnavailable,
+}
+
+// ============================================================================
+// BUDGET TEMPS PAR ANALYSE
+// ============================================================================
+
+/// Limite de temps partagée par tous les appels RPC (et leurs retries) d'une même analyse
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    expires_at: tokio::time::Instant,
+    budget: Duration,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Self {
+            expires_at: tokio::time::Instant::now() + budget,
+            budget,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(tokio::time::Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    fn timeout_error(&self) -> anyhow::Error {
+        AnalysisError::RpcTimeout { budget_ms: self.budget.as_millis() as u64 }.into()
+    }
+
+    /// Exécute un appel dans le temps restant
+    pub async fn run<T, F>(&self, fut: F) -> anyhow::Result<T>
+    where
+        F: std::future::Future<Output = anyhow::Result<T>>,
+    {
+        if self.is_expired() {
+            return Err(self.timeout_error());
+        }
+        match tokio::time::timeout(self.remaining(), fut).await {
+            Ok(result) => result,
+            Err(_) => Err(self.timeout_error()),
+        }
+    }
+
+    /// Exécute un appel avec retries (backoff exponentiel), sans jamais dépasser le budget
+    pub async fn retry<T, F, Fut>(&self, max_attempts: u32, mut call: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+    {
+        let mut backoff = Duration::from_millis(50);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.run(call()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is::<AnalysisError>() || attempt >= max_attempts.max(1) => return Err(e),
+                Err(e) => {
+                    log::debug!("Tentative {} échouée: {}", attempt, e);
+                    tokio::time::sleep(backoff.min(self.remaining())).await;
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// DÉDOUBLONNAGE DES SIGNATURES
+// ============================================================================
+
+/// Signatures déjà vues, bornées en nombre (les plus anciennes sont évincées) et en âge
+#[derive(Debug)]
+pub struct SeenSignatures {
+    capacity: usize,
+    ttl: Duration,
+    entries: ahash::AHashMap<String, Instant>,
+    order: std::collections::VecDeque<String>,
+    lookups: u64,
+    hits: u64,
+    // Entrées évincées faute de place avant d'avoir expiré : le store est trop petit
+    capacity_evictions: u64,
+}
+
+/// Métriques du store de signatures
+#[derive(Debug, Clone, Copy)]
+pub struct SeenSignaturesStats {
+    pub len: usize,
+    pub capacity: usize,
+    pub lookups: u64,
+    pub hits: u64,
+    pub capacity_evictions: u64,
+}
+
+impl SeenSignaturesStats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups as f64
+        }
+    }
+}
+
+impl SeenSignatures {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: ahash::AHashMap::with_capacity(capacity),
+            order: std::collections::VecDeque::with_capacity(capacity),
+            lookups: 0,
+            hits: 0,
+            capacity_evictions: 0,
+        }
+    }
+
+    /// Vrai si la signature a déjà été vue dans la fenêtre ; sinon l'enregistre
+    pub fn check_and_insert(&mut self, signature: &str) -> bool {
+        let now = Instant::now();
+        self.evict(now);
+        self.lookups += 1;
+
+        if self.entries.contains_key(signature) {
+            self.hits += 1;
+            return true;
+        }
+        if self.capacity == 0 {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+                self.capacity_evictions += 1;
+            }
+        }
+        self.entries.insert(signature.to_string(), now);
+        self.order.push_back(signature.to_string());
+        false
+    }
+
+    /// Retire les entrées plus vieilles que le TTL (insérées dans l'ordre, donc en tête de file)
+    fn evict(&mut self, now: Instant) {
+        while let Some(oldest) = self.order.front() {
+            match self.entries.get(oldest) {
+                Some(seen_at) if now.duration_since(*seen_at) < self.ttl => break,
+                _ => {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.entries.remove(&oldest);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> SeenSignaturesStats {
+        SeenSignaturesStats {
+            len: self.order.len(),
+            capacity: self.capacity,
+            lookups: self.lookups,
+            hits: self.hits,
+            capacity_evictions: self.capacity_evictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seen_signatures_evicts_the_oldest_then_expired_entries() {
+        let mut seen = SeenSignatures::new(2, Duration::from_secs(60));
+        assert!(!seen.check_and_insert("a"));
+        assert!(!seen.check_and_insert("b"));
+        assert!(seen.check_and_insert("a"));
+        // Plein : la plus ancienne insertion ("a") laisse sa place
+        assert!(!seen.check_and_insert("c"));
+        assert!(!seen.check_and_insert("a"));
+        assert!(seen.check_and_insert("c"));
+        let stats = seen.stats();
+        assert_eq!((stats.len, stats.lookups, stats.hits, stats.capacity_evictions), (2, 6, 2, 2));
+        assert!((stats.hit_rate() - 2.0 / 6.0).abs() < 1e-12);
+
+        let mut seen = SeenSignatures::new(10, Duration::from_millis(20));
+        assert!(!seen.check_and_insert("a"));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(!seen.check_and_insert("a"));
+        assert_eq!(seen.stats().capacity_evictions, 0);
+
+        // Capacité nulle : rien n'est retenu
+        let mut seen = SeenSignatures::new(0, Duration::from_secs(60));
+        assert!(!seen.check_and_insert("a"));
+        assert!(!seen.check_and_insert("a"));
+    }
 }